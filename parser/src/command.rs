@@ -4,14 +4,20 @@ use crate::token::Tokenizer;
 use regex::Regex;
 
 pub mod assign;
+pub mod cherry_pick;
 pub mod close;
+pub mod crater;
+pub mod edition_compat;
 pub mod glacier;
 pub mod nominate;
 pub mod note;
+pub mod on_call;
+pub mod perf;
 pub mod ping;
 pub mod prioritize;
 pub mod relabel;
 pub mod second;
+pub mod semver;
 pub mod shortcut;
 pub mod transfer;
 
@@ -28,6 +34,12 @@ pub enum Command<'a> {
     Close(Result<close::CloseCommand, Error<'a>>),
     Note(Result<note::NoteCommand, Error<'a>>),
     Transfer(Result<transfer::TransferCommand, Error<'a>>),
+    Perf(Result<perf::PerfCommand, Error<'a>>),
+    CherryPick(Result<cherry_pick::CherryPickCommand, Error<'a>>),
+    OnCall(Result<on_call::WhoIsOnCallCommand, Error<'a>>),
+    Semver(Result<semver::SemverCommand, Error<'a>>),
+    Crater(Result<crater::CraterCommand, Error<'a>>),
+    EditionCompat(Result<edition_compat::EditionCompatCommand, Error<'a>>),
 }
 
 #[derive(Debug)]
@@ -139,6 +151,36 @@ impl<'a> Input<'a> {
             Command::Transfer,
             &original_tokenizer,
         ));
+        success.extend(parse_single_command(
+            perf::PerfCommand::parse,
+            Command::Perf,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            cherry_pick::CherryPickCommand::parse,
+            Command::CherryPick,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            on_call::WhoIsOnCallCommand::parse,
+            Command::OnCall,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            semver::SemverCommand::parse,
+            Command::Semver,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            crater::CraterCommand::parse,
+            Command::Crater,
+            &original_tokenizer,
+        ));
+        success.extend(parse_single_command(
+            edition_compat::EditionCompatCommand::parse,
+            Command::EditionCompat,
+            &original_tokenizer,
+        ));
 
         if success.len() > 1 {
             panic!(
@@ -170,18 +212,21 @@ impl<'a> Input<'a> {
             }
         }
     }
-}
 
-impl<'a> Iterator for Input<'a> {
-    type Item = Command<'a>;
+    /// Like iterating over the input, but also yields the text each command
+    /// was parsed from, starting at the bot mention (or `r?`).
+    pub fn with_source(mut self) -> impl Iterator<Item = (Command<'a>, &'a str)> {
+        std::iter::from_fn(move || self.next_with_source())
+    }
 
-    fn next(&mut self) -> Option<Command<'a>> {
+    fn next_with_source(&mut self) -> Option<(Command<'a>, &'a str)> {
         loop {
             let caps = self.bot_re.captures(&self.all[self.parsed..])?;
             let m = caps.get(0).unwrap();
+            let start = self.parsed + m.start();
             if self
                 .ignore
-                .overlaps_ignore((self.parsed + m.start())..(self.parsed + m.end()))
+                .overlaps_ignore(start..(self.parsed + m.end()))
                 .is_some()
             {
                 log::info!("command overlaps ignored block; ignore: {:?}", self.ignore);
@@ -190,17 +235,26 @@ impl<'a> Iterator for Input<'a> {
             }
 
             self.parsed += m.end();
-            if caps.name("review").is_some() {
-                if let Some(command) = self.parse_review() {
-                    return Some(command);
-                }
-            } else if let Some(command) = self.parse_command() {
-                return Some(command);
+            let command = if caps.name("review").is_some() {
+                self.parse_review()
+            } else {
+                self.parse_command()
+            };
+            if let Some(command) = command {
+                return Some((command, self.all[start..self.parsed].trim_end()));
             }
         }
     }
 }
 
+impl<'a> Iterator for Input<'a> {
+    type Item = Command<'a>;
+
+    fn next(&mut self) -> Option<Command<'a>> {
+        self.next_with_source().map(|(command, _)| command)
+    }
+}
+
 impl<'a> Command<'a> {
     pub fn is_ok(&self) -> bool {
         match self {
@@ -215,6 +269,12 @@ impl<'a> Command<'a> {
             Command::Close(r) => r.is_ok(),
             Command::Note(r) => r.is_ok(),
             Command::Transfer(r) => r.is_ok(),
+            Command::Perf(r) => r.is_ok(),
+            Command::CherryPick(r) => r.is_ok(),
+            Command::OnCall(r) => r.is_ok(),
+            Command::Semver(r) => r.is_ok(),
+            Command::Crater(r) => r.is_ok(),
+            Command::EditionCompat(r) => r.is_ok(),
         }
     }
 
@@ -308,6 +368,14 @@ fn multiname() {
     assert!(input.next().is_none());
 }
 
+#[test]
+fn command_source() {
+    let input = "Looks good. @bot labels: +bug. Afterwards, r? @octocat";
+    let input = Input::new(input, vec!["bot"]);
+    let sources: Vec<_> = input.with_source().map(|(_, source)| source).collect();
+    assert_eq!(sources, ["@bot labels: +bug.", "r? @octocat"]);
+}
+
 #[test]
 fn review_commands() {
     for (input, name) in [
@@ -0,0 +1,73 @@
+//! Parses the `@bot cherry-pick <branch>` command, requesting a backport of a
+//! merged PR to another branch.
+//!
+//! Branch names containing punctuation (like `1.75`) need to be quoted.
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CherryPickCommand(pub String);
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingBranch,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingBranch => write!(f, "missing branch name"),
+        }
+    }
+}
+
+impl CherryPickCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("cherry-pick"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        match input.next_token()? {
+            Some(Token::Word(branch) | Token::Quote(branch)) => {
+                Ok(Some(CherryPickCommand(branch.to_owned())))
+            }
+            _ => Err(input.error(ParseError::MissingBranch)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<CherryPickCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(CherryPickCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parses_branch() {
+    assert_eq!(
+        parse("cherry-pick beta"),
+        Ok(Some(CherryPickCommand("beta".to_string())))
+    );
+    assert_eq!(
+        parse("cherry-pick \"release-1.75\""),
+        Ok(Some(CherryPickCommand("release-1.75".to_string())))
+    );
+    assert_eq!(parse("cherry pick beta"), Ok(None));
+}
+
+#[test]
+fn missing_branch() {
+    use std::error::Error;
+    assert_eq!(
+        parse("cherry-pick")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::MissingBranch),
+    );
+}
@@ -0,0 +1,102 @@
+//! Parses the `@bot crater <mode>` command, requesting a crater run of a PR.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot crater <mode>`, where `<mode>` is one of crater's
+//! experiment modes, e.g. `build-and-test` or `check-only`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+/// The experiment modes crater supports.
+pub const MODES: &[&str] = &[
+    "build-and-test",
+    "build-only",
+    "check-only",
+    "clippy",
+    "rustdoc",
+    "unstable-features",
+    "fix",
+];
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct CraterCommand {
+    pub mode: String,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingMode,
+    UnknownMode,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingMode => write!(f, "missing experiment mode"),
+            ParseError::UnknownMode => {
+                write!(f, "unknown experiment mode, expected one of: ")?;
+                write!(f, "{}", MODES.join(", "))
+            }
+        }
+    }
+}
+
+impl CraterCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("crater"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        match input.next_token()? {
+            Some(Token::Word(mode)) if MODES.contains(&mode) => Ok(Some(CraterCommand {
+                mode: mode.to_owned(),
+            })),
+            Some(Token::Word(_)) => Err(input.error(ParseError::UnknownMode)),
+            _ => Err(input.error(ParseError::MissingMode)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<CraterCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(CraterCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parses_mode() {
+    assert_eq!(
+        parse("crater check-only"),
+        Ok(Some(CraterCommand {
+            mode: "check-only".to_string()
+        }))
+    );
+    assert_eq!(parse("craters"), Ok(None));
+}
+
+#[test]
+fn rejects_bad_modes() {
+    use std::error::Error;
+    assert_eq!(
+        parse("crater")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::MissingMode),
+    );
+    assert_eq!(
+        parse("crater everything")
+            .unwrap_err()
+            .source()
+            .unwrap()
+            .downcast_ref(),
+        Some(&ParseError::UnknownMode),
+    );
+}
@@ -0,0 +1,113 @@
+//! Parses the `@bot edition-compat` command, which tracks whether an item is
+//! ready for the next edition.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot edition-compat <status>` or `@bot edition-compat list`,
+//! where `<status>` is `compatible`, `needs-changes` or `blocked`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum EditionCompatCommand {
+    Set(EditionCompatStatus),
+    List,
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EditionCompatStatus {
+    Compatible,
+    NeedsChanges,
+    Blocked,
+}
+
+impl EditionCompatStatus {
+    pub const ALL: [EditionCompatStatus; 3] = [
+        EditionCompatStatus::Compatible,
+        EditionCompatStatus::NeedsChanges,
+        EditionCompatStatus::Blocked,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EditionCompatStatus::Compatible => "compatible",
+            EditionCompatStatus::NeedsChanges => "needs-changes",
+            EditionCompatStatus::Blocked => "blocked",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    ExpectedStatus,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::ExpectedStatus => write!(
+                f,
+                "expected one of `compatible`, `needs-changes`, `blocked` or `list`"
+            ),
+        }
+    }
+}
+
+impl EditionCompatCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("edition-compat"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        let cmd = match input.next_token()? {
+            Some(Token::Word("list")) => EditionCompatCommand::List,
+            Some(Token::Word(word)) => match EditionCompatStatus::ALL
+                .into_iter()
+                .find(|status| status.as_str() == word)
+            {
+                Some(status) => EditionCompatCommand::Set(status),
+                None => return Err(input.error(ParseError::ExpectedStatus)),
+            },
+            _ => return Err(input.error(ParseError::ExpectedStatus)),
+        };
+        Ok(Some(cmd))
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<EditionCompatCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(EditionCompatCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parses_status_and_list() {
+    assert_eq!(
+        parse("edition-compat needs-changes"),
+        Ok(Some(EditionCompatCommand::Set(
+            EditionCompatStatus::NeedsChanges
+        )))
+    );
+    assert_eq!(
+        parse("edition-compat list"),
+        Ok(Some(EditionCompatCommand::List))
+    );
+    assert_eq!(parse("edition compat"), Ok(None));
+}
+
+#[test]
+fn unknown_status() {
+    use std::error::Error;
+    for input in ["edition-compat", "edition-compat done"] {
+        assert_eq!(
+            parse(input).unwrap_err().source().unwrap().downcast_ref(),
+            Some(&ParseError::ExpectedStatus),
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! Parses the `@bot who-is-on-call` command.
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct WhoIsOnCallCommand;
+
+impl WhoIsOnCallCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if let Some(Token::Word("who-is-on-call")) = input.peek_token()? {
+            input.next_token()?;
+            Ok(Some(Self))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<WhoIsOnCallCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(WhoIsOnCallCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parses_who_is_on_call() {
+    assert_eq!(parse("who-is-on-call"), Ok(Some(WhoIsOnCallCommand)));
+    assert_eq!(parse("who-is-on-call?"), Ok(Some(WhoIsOnCallCommand)));
+    assert_eq!(parse("who is on call"), Ok(None));
+}
@@ -0,0 +1,31 @@
+//! Parses the `@bot perf` command, requesting a performance run for a PR.
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct PerfCommand;
+
+impl PerfCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if let Some(Token::Word("perf")) = input.peek_token()? {
+            input.next_token()?;
+            Ok(Some(Self))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<PerfCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(PerfCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parses_perf() {
+    assert_eq!(parse("perf"), Ok(Some(PerfCommand)));
+    assert_eq!(parse("perf."), Ok(Some(PerfCommand)));
+    assert_eq!(parse("performance"), Ok(None));
+}
@@ -0,0 +1,76 @@
+//! Parses the `@bot semver <kind>` command, which labels a PR with the kind
+//! of semver change it makes.
+//!
+//! The grammar is as follows:
+//!
+//! ```text
+//! Command: `@bot semver breaking`, `@bot semver minor` or `@bot semver patch`.
+//! ```
+
+use crate::error::Error;
+use crate::token::{Token, Tokenizer};
+use std::fmt;
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum SemverCommand {
+    Breaking,
+    Minor,
+    Patch,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum ParseError {
+    MissingKind,
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingKind => {
+                write!(f, "expected one of `breaking`, `minor` or `patch`")
+            }
+        }
+    }
+}
+
+impl SemverCommand {
+    pub fn parse<'a>(input: &mut Tokenizer<'a>) -> Result<Option<Self>, Error<'a>> {
+        if !matches!(input.peek_token()?, Some(Token::Word("semver"))) {
+            return Ok(None);
+        }
+        input.next_token()?;
+        match input.next_token()? {
+            Some(Token::Word("breaking")) => Ok(Some(SemverCommand::Breaking)),
+            Some(Token::Word("minor")) => Ok(Some(SemverCommand::Minor)),
+            Some(Token::Word("patch")) => Ok(Some(SemverCommand::Patch)),
+            _ => Err(input.error(ParseError::MissingKind)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Result<Option<SemverCommand>, Error<'_>> {
+    let mut toks = Tokenizer::new(input);
+    Ok(SemverCommand::parse(&mut toks)?)
+}
+
+#[test]
+fn parses_kind() {
+    assert_eq!(parse("semver breaking"), Ok(Some(SemverCommand::Breaking)));
+    assert_eq!(parse("semver minor."), Ok(Some(SemverCommand::Minor)));
+    assert_eq!(parse("semver patch"), Ok(Some(SemverCommand::Patch)));
+    assert_eq!(parse("semantic versioning"), Ok(None));
+}
+
+#[test]
+fn missing_kind() {
+    use std::error::Error;
+    for input in ["semver", "semver major"] {
+        assert_eq!(
+            parse(input).unwrap_err().source().unwrap().downcast_ref(),
+            Some(&ParseError::MissingKind),
+        );
+    }
+}
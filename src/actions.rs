@@ -125,6 +125,7 @@ impl<'a> Action for Step<'a> {
                     // These are unused for query.
                     default_branch: "master".to_string(),
                     fork: false,
+                    private: false,
                     parent: None,
                 };
 
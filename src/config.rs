@@ -45,6 +45,36 @@ pub(crate) struct Config {
     pub(crate) validate_config: Option<ValidateConfig>,
     pub(crate) pr_tracking: Option<ReviewPrefsConfig>,
     pub(crate) transfer: Option<TransferConfig>,
+    pub(crate) keyword_label: Option<Vec<KeywordLabelConfig>>,
+    pub(crate) milestone: Option<MilestoneConfig>,
+    pub(crate) ci_status: Option<FlakyCiConfig>,
+    pub(crate) perf: Option<PerfConfig>,
+    pub(crate) feature_tracking: Option<FeatureTrackingConfig>,
+    pub(crate) meeting_agenda: Option<MeetingAgendaConfig>,
+    pub(crate) contributor_digest: Option<ContributorDigestConfig>,
+    pub(crate) push_trigger: Option<Vec<PathTriggeredJobConfig>>,
+    pub(crate) required_review: Option<Vec<RequiredReviewConfig>>,
+    pub(crate) auto_review_request: Option<Vec<AutoReviewRequestConfig>>,
+    pub(crate) conflict_notify: Option<ConflictNotifyConfig>,
+    pub(crate) cherry_pick: Option<CherryPickConfig>,
+    pub(crate) regression_link: Option<RegressionLinkConfig>,
+    pub(crate) priority_scoring: Option<PriorityScoringConfig>,
+    pub(crate) sla: Option<SlaConfig>,
+    pub(crate) on_call: Option<OnCallConfig>,
+    pub(crate) dep_audit: Option<DepAuditConfig>,
+    pub(crate) msrv: Option<MsrvConfig>,
+    pub(crate) changelog: Option<ChangelogConfig>,
+    pub(crate) semver: Option<SemverConfig>,
+    pub(crate) toolchain_update: Option<ToolchainUpdateConfig>,
+    pub(crate) crater: Option<CraterConfig>,
+    pub(crate) needs_info: Option<NeedsInfoConfig>,
+    pub(crate) edition: Option<EditionTrackingConfig>,
+    pub(crate) rollup: Option<RollupConfig>,
+    /// Restricts who may run a command, keyed by the command's section name
+    /// (e.g. `relabel`). Values are GitHub logins, team names, or `*` for
+    /// any team member.
+    #[serde(default)]
+    pub(crate) command_permissions: HashMap<String, Vec<String>>,
 }
 
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
@@ -218,6 +248,27 @@ pub(crate) struct AutolabelLabelConfig {
     pub(crate) new_issue: bool,
 }
 
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct KeywordLabelConfig {
+    /// Words or phrases that trigger the label. Matching is case-insensitive
+    /// and also finds keywords inside longer words.
+    pub(crate) keywords: Vec<String>,
+    /// The label to apply when any of the keywords match.
+    pub(crate) label: String,
+    /// Whether to look for the keywords in the issue title.
+    #[serde(default = "default_true")]
+    pub(crate) match_title: bool,
+    /// Whether to look for the keywords in the issue body.
+    #[serde(default = "default_true")]
+    pub(crate) match_body: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 pub(crate) struct NotifyZulipConfig {
     #[serde(flatten)]
@@ -289,6 +340,376 @@ impl MajorChangeConfig {
     }
 }
 
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MilestoneConfig {
+    /// Regular expression matched against the labels of a merged PR. It must
+    /// contain a `version` capture group.
+    pub(crate) label_pattern: String,
+    /// Title of the milestone to assign, with `{version}` replaced by the
+    /// version captured from the label.
+    pub(crate) milestone_match: String,
+    /// Label applied when no open milestone matches.
+    #[serde(default = "MilestoneConfig::needs_milestone_label_default")]
+    pub(crate) needs_milestone_label: String,
+}
+
+impl MilestoneConfig {
+    fn needs_milestone_label_default() -> String {
+        String::from("needs-milestone")
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FlakyCiConfig {
+    /// Regular expressions matched against the output of failed check runs.
+    pub(crate) patterns: Vec<String>,
+    /// Label applied to the PR when a pattern matches.
+    #[serde(default = "FlakyCiConfig::label_default")]
+    pub(crate) label: String,
+    /// Who to ping when the same check fails again with the same pattern.
+    #[serde(default = "FlakyCiConfig::escalate_to_default")]
+    pub(crate) escalate_to: String,
+}
+
+impl FlakyCiConfig {
+    fn label_default() -> String {
+        String::from("flaky-test")
+    }
+    fn escalate_to_default() -> String {
+        String::from("@rust-lang/infra")
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PerfConfig {
+    /// Who to ping when a perf run is requested, e.g. `@rust-lang/wg-compiler-performance`.
+    pub(crate) ping: String,
+    /// The account that posts perf results.
+    #[serde(default = "PerfConfig::bot_username_default")]
+    pub(crate) bot_username: String,
+    #[serde(default = "PerfConfig::regression_label_default")]
+    pub(crate) regression_label: String,
+    #[serde(default = "PerfConfig::improvement_label_default")]
+    pub(crate) improvement_label: String,
+}
+
+impl PerfConfig {
+    fn bot_username_default() -> String {
+        String::from("rust-timer")
+    }
+    fn regression_label_default() -> String {
+        String::from("perf-regression")
+    }
+    fn improvement_label_default() -> String {
+        String::from("perf-improvement")
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FeatureTrackingConfig {
+    /// Who to ping about tracking issues that have gone stale.
+    pub(crate) ping: String,
+    /// The label marking an issue as a tracking issue.
+    #[serde(default = "FeatureTrackingConfig::label_default")]
+    pub(crate) label: String,
+    /// Regular expression with a `feature` capture group, matched against the
+    /// issue title to find the feature name.
+    #[serde(default = "FeatureTrackingConfig::feature_pattern_default")]
+    pub(crate) feature_pattern: String,
+    /// The checklist to post on new tracking issues. `{feature}` is replaced
+    /// with the feature name.
+    #[serde(default = "FeatureTrackingConfig::checklist_default")]
+    pub(crate) checklist: String,
+}
+
+impl FeatureTrackingConfig {
+    fn label_default() -> String {
+        String::from("C-tracking-issue")
+    }
+    pub(crate) fn feature_pattern_default() -> String {
+        String::from(r"(?i)tracking issue for\W+(?:feature\()?`?(?P<feature>[a-z0-9_]+)")
+    }
+    fn checklist_default() -> String {
+        String::from(
+            "### Steps for `{feature}`\n\n\
+             - [ ] Implement the feature\n\
+             - [ ] Adjust documentation\n\
+             - [ ] Write a stabilization report\n\
+             - [ ] Stabilization PR\n",
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MeetingAgendaConfig {
+    /// Issues labelled `{team}-nominated` are put on the agenda.
+    pub(crate) team: String,
+    /// The issue the agenda is posted on.
+    pub(crate) agenda_issue_number: u64,
+    /// When to post a new agenda, as a cron expression (e.g. `0 0 9 * * Mon *`).
+    pub(crate) schedule: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ContributorDigestConfig {
+    /// The issue the weekly digest is posted on.
+    pub(crate) digest_issue_number: u64,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PathTriggeredJobConfig {
+    /// A glob matched against the paths added or modified by a push to the
+    /// default branch, e.g. `src/doc/**`.
+    pub(crate) path_glob: String,
+    /// The name of the job to schedule, as registered in `jobs::jobs()`.
+    pub(crate) job_name: String,
+    /// How long after the push the job should run.
+    #[serde(default)]
+    pub(crate) delay_seconds: u64,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RequiredReviewConfig {
+    /// PRs with this label need approval from `team`.
+    pub(crate) label: String,
+    /// The team whose members' approvals count, as named in the team repo.
+    pub(crate) team: String,
+    /// How many team members need to approve.
+    pub(crate) min_approvals: u32,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AutoReviewRequestConfig {
+    /// Adding this label to a PR requests reviews from `team`.
+    pub(crate) label: String,
+    /// The team to request reviews from, as named in the team repo.
+    pub(crate) team: String,
+    /// If set, only this many randomly chosen team members are requested.
+    #[serde(default)]
+    pub(crate) max_reviewers: Option<usize>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ConflictNotifyConfig {
+    /// Posted when a PR starts conflicting; `{author}` is replaced with the
+    /// PR author's login.
+    pub(crate) comment_template: String,
+    /// Applied to PRs while they have conflicts.
+    pub(crate) label: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CherryPickConfig {
+    /// The branches merged PRs may be backported to with `cherry-pick`.
+    pub(crate) allowed_base_branches: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RegressionLinkConfig {
+    /// Whether new issues mentioning commit SHAs get a comment linking the
+    /// PRs that introduced them.
+    pub(crate) enabled: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PriorityScoringConfig {
+    pub(crate) rules: Vec<ScoringRule>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ScoringRule {
+    /// Matched case-insensitively against the issue's title and body. An
+    /// empty keyword matches every issue.
+    pub(crate) keyword: String,
+    /// If set, the rule only applies to issues with this label.
+    #[serde(default)]
+    pub(crate) label_if_present: Option<String>,
+    /// Added to the issue's score when the rule applies.
+    pub(crate) score_delta: i32,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SlaConfig {
+    /// How long a new issue may go without a comment from a team member.
+    pub(crate) response_sla_hours: u32,
+    /// Added to issues that weren't answered in time.
+    pub(crate) escalation_label: String,
+    /// The team pinged about issues that weren't answered in time.
+    pub(crate) escalation_team: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct OnCallConfig {
+    /// The team whose members take turns being on call, as named in the
+    /// team repo.
+    pub(crate) team: String,
+    /// How many weeks each member stays on call.
+    pub(crate) rotation_weeks: u32,
+    /// The label of the issues the on-call member looks after, linked from
+    /// the announcement.
+    #[serde(default)]
+    pub(crate) on_call_label: Option<String>,
+    /// If set, each new on-call member is announced on this issue.
+    #[serde(default)]
+    pub(crate) announcement_issue: Option<u64>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DepAuditConfig {
+    /// Labels added to the issues opened for vulnerable dependencies.
+    #[serde(default)]
+    pub(crate) labels: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MsrvConfig {
+    /// Added to PRs that raise the `rust-version` in `Cargo.toml`.
+    #[serde(default = "MsrvConfig::msrv_label_default")]
+    pub(crate) msrv_label: String,
+    /// The toolchain file whose version is the lowest `rust-version` PRs may
+    /// set.
+    #[serde(default = "MsrvConfig::msrv_file_default")]
+    pub(crate) msrv_file: String,
+    /// Who to ping when a PR raises the MSRV, e.g. `@rust-lang/libs`.
+    #[serde(default)]
+    pub(crate) ping: Option<String>,
+}
+
+impl MsrvConfig {
+    fn msrv_label_default() -> String {
+        String::from("msrv-bump")
+    }
+    fn msrv_file_default() -> String {
+        String::from("rust-toolchain.toml")
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ChangelogConfig {
+    /// PRs with any of these labels must update the changelog.
+    pub(crate) required_for_labels: Vec<String>,
+    /// The path of the changelog, e.g. `CHANGELOG.md`.
+    pub(crate) changelog_file: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SemverConfig {
+    /// Added to PRs that make breaking changes, either because they mention
+    /// `BREAKING CHANGE:` or through `@rustbot semver breaking`.
+    pub(crate) breaking_label: String,
+    pub(crate) minor_label: String,
+    pub(crate) patch_label: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ToolchainUpdateConfig {
+    /// The channels whose new releases are announced.
+    pub(crate) channels: Vec<ToolchainChannel>,
+    /// The issue new releases are announced on.
+    pub(crate) tracking_issue: u64,
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ToolchainChannel {
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl ToolchainChannel {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ToolchainChannel::Stable => "stable",
+            ToolchainChannel::Beta => "beta",
+            ToolchainChannel::Nightly => "nightly",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CraterConfig {
+    /// The teams whose members may request crater runs.
+    pub(crate) allowed_teams: Vec<String>,
+    /// The repository crater run requests are opened in, e.g.
+    /// `rust-lang/crater-requests`.
+    pub(crate) crater_repo: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NeedsInfoConfig {
+    /// The label marking issues that wait on their author, e.g. `needs-info`.
+    pub(crate) label: String,
+    /// How long the author has to respond before the issue is closed.
+    pub(crate) days_before_close: u32,
+    /// Posted when an issue is closed for lack of a response.
+    pub(crate) close_comment: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EditionTrackingConfig {
+    /// The issue whose summary comment is kept up to date.
+    pub(crate) tracking_issue: u64,
+    /// The edition being tracked, e.g. `2024`.
+    pub(crate) next_edition: String,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RollupConfig {
+    /// The label marking rollup PRs, e.g. `rollup`.
+    pub(crate) rollup_label: String,
+}
+
 #[derive(PartialEq, Eq, Debug, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct GlacierConfig {}
@@ -527,7 +948,49 @@ mod tests {
                 validate_config: Some(ValidateConfig {}),
                 pr_tracking: None,
                 transfer: None,
+                keyword_label: None,
+                milestone: None,
+                ci_status: None,
+                perf: None,
+                feature_tracking: None,
+                meeting_agenda: None,
+                contributor_digest: None,
+                push_trigger: None,
+                required_review: None,
+                auto_review_request: None,
+                conflict_notify: None,
+                cherry_pick: None,
+                regression_link: None,
+                priority_scoring: None,
+                sla: None,
+                on_call: None,
+                dep_audit: None,
+                msrv: None,
+                changelog: None,
+                semver: None,
+                toolchain_update: None,
+                crater: None,
+                needs_info: None,
+                edition: None,
+                rollup: None,
+                command_permissions: HashMap::new(),
             }
         );
     }
+
+    #[test]
+    fn command_permissions() {
+        let config = r#"
+            [command-permissions]
+            major-change = ["*"]
+            close = ["release", "octocat"]
+        "#;
+        let config = toml::from_str::<Config>(&config).unwrap();
+        assert_eq!(config.command_permissions["major-change"], vec!["*"]);
+        assert_eq!(
+            config.command_permissions["close"],
+            vec!["release", "octocat"]
+        );
+        assert!(!config.command_permissions.contains_key("relabel"));
+    }
 }
@@ -3,13 +3,24 @@ use anyhow::Context as _;
 use chrono::Utc;
 use native_tls::{Certificate, TlsConnector};
 use postgres_native_tls::MakeTlsConnector;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio_postgres::Client as DbClient;
+use tracing::Instrument;
 
+pub mod audit;
+pub mod changelog_nags;
+pub mod client;
+pub mod conflict_notifications;
 pub mod issue_data;
+pub mod job_encryption;
 pub mod jobs;
+pub mod migrations;
 pub mod notifications;
+pub mod rate_limit;
+pub mod registered_repos;
 pub mod rustc_commits;
 
 const CERT_URL: &str = "https://truststore.pki.rds.amazonaws.com/global/global-bundle.pem";
@@ -25,9 +36,129 @@ lazy_static::lazy_static! {
     };
 }
 
+/// Settings for the database connection pool.
+///
+/// Read from the `TRIAGEBOT_DB_POOL_MAX_SIZE` and
+/// `TRIAGEBOT_DB_CONNECTION_TIMEOUT_MS` environment variables.
+#[derive(Debug, Clone)]
+pub struct DbPoolConfig {
+    /// The database to connect to. `DATABASE_URL` is used if this is unset.
+    pub database_url: Option<String>,
+    /// The maximum number of open connections.
+    pub max_size: usize,
+    /// How long [`ClientPool::try_get`] waits for a connection.
+    pub connection_timeout_ms: u64,
+}
+
+impl Default for DbPoolConfig {
+    fn default() -> Self {
+        DbPoolConfig {
+            database_url: None,
+            max_size: 16,
+            connection_timeout_ms: 5000,
+        }
+    }
+}
+
+impl DbPoolConfig {
+    pub fn from_env() -> DbPoolConfig {
+        let default = DbPoolConfig::default();
+        DbPoolConfig {
+            database_url: None,
+            max_size: env_or("TRIAGEBOT_DB_POOL_MAX_SIZE", default.max_size),
+            connection_timeout_ms: env_or(
+                "TRIAGEBOT_DB_CONNECTION_TIMEOUT_MS",
+                default.connection_timeout_ms,
+            ),
+        }
+    }
+}
+
+/// Settings for running scheduled jobs.
+///
+/// Read from the `TRIAGEBOT_JOB_*` environment variables.
+#[derive(Debug, Clone)]
+pub struct JobRunnerConfig {
+    /// How many due jobs are fetched per poll.
+    pub batch_size: i64,
+    /// How long to wait before running a failed job again.
+    pub retry_minutes: i32,
+    /// How many times a failed job is run again before it is left alone.
+    pub max_retries: i32,
+    /// How long a job may run, unless it overrides [`crate::jobs::Job::timeout`].
+    pub timeout_seconds: u64,
+    /// How many jobs may run at the same time.
+    pub max_concurrent_jobs: usize,
+    /// How many jobs may wait for a free slot; the rest wait for the next
+    /// tick.
+    pub max_queue_size: usize,
+    /// How long running jobs may take to finish once a shutdown has been
+    /// requested.
+    pub shutdown_grace_seconds: u64,
+}
+
+impl Default for JobRunnerConfig {
+    fn default() -> Self {
+        JobRunnerConfig {
+            batch_size: 100,
+            retry_minutes: 60,
+            max_retries: 5,
+            timeout_seconds: 5 * 60,
+            max_concurrent_jobs: 5,
+            max_queue_size: 50,
+            shutdown_grace_seconds: 60,
+        }
+    }
+}
+
+impl JobRunnerConfig {
+    pub fn from_env() -> JobRunnerConfig {
+        let default = JobRunnerConfig::default();
+        JobRunnerConfig {
+            batch_size: env_or("TRIAGEBOT_JOB_BATCH_SIZE", default.batch_size),
+            retry_minutes: env_or("TRIAGEBOT_JOB_RETRY_MINUTES", default.retry_minutes),
+            max_retries: env_or("TRIAGEBOT_JOB_MAX_RETRIES", default.max_retries),
+            timeout_seconds: env_or("TRIAGEBOT_JOB_TIMEOUT_SECONDS", default.timeout_seconds),
+            max_concurrent_jobs: env_or(
+                "TRIAGEBOT_JOB_MAX_CONCURRENT",
+                default.max_concurrent_jobs,
+            ),
+            max_queue_size: env_or("TRIAGEBOT_JOB_MAX_QUEUE_SIZE", default.max_queue_size),
+            shutdown_grace_seconds: env_or(
+                "TRIAGEBOT_JOB_SHUTDOWN_GRACE_SECONDS",
+                default.shutdown_grace_seconds,
+            ),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(v) => v.parse().unwrap_or_else(|_| {
+            tracing::warn!("ignoring invalid value for {name}: {v:?}");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// A snapshot of the pool's usage.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PoolStatus {
+    pub max_size: usize,
+    /// Connections that can be handed out without waiting.
+    pub available: usize,
+    /// Tasks currently waiting for a connection.
+    pub waiting: usize,
+}
+
 pub struct ClientPool {
+    database_url: Option<String>,
     connections: Arc<Mutex<Vec<tokio_postgres::Client>>>,
     permits: Arc<Semaphore>,
+    max_size: usize,
+    connection_timeout: Duration,
+    waiting: AtomicUsize,
 }
 
 pub struct PooledClient {
@@ -60,39 +191,81 @@ impl std::ops::DerefMut for PooledClient {
 
 impl ClientPool {
     pub fn new() -> ClientPool {
+        ClientPool::with_config(DbPoolConfig::from_env())
+    }
+
+    pub fn with_config(config: DbPoolConfig) -> ClientPool {
         ClientPool {
-            connections: Arc::new(Mutex::new(Vec::with_capacity(16))),
-            permits: Arc::new(Semaphore::new(16)),
+            database_url: config.database_url,
+            connections: Arc::new(Mutex::new(Vec::with_capacity(config.max_size))),
+            permits: Arc::new(Semaphore::new(config.max_size)),
+            max_size: config.max_size,
+            connection_timeout: Duration::from_millis(config.connection_timeout_ms),
+            waiting: AtomicUsize::new(0),
         }
     }
 
     pub async fn get(&self) -> PooledClient {
-        let permit = self.permits.clone().acquire_owned().await.unwrap();
+        self.get_inner().await.unwrap()
+    }
+
+    /// Like [`ClientPool::get`], but gives up after the configured connection
+    /// timeout and returns connection errors instead of panicking.
+    pub async fn try_get(&self) -> anyhow::Result<PooledClient> {
+        tokio::time::timeout(self.connection_timeout, self.get_inner())
+            .await
+            .context("timed out waiting for a database connection")?
+    }
+
+    pub fn status(&self) -> PoolStatus {
+        PoolStatus {
+            max_size: self.max_size,
+            available: self.permits.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn get_inner(&self) -> anyhow::Result<PooledClient> {
+        let permit = {
+            self.waiting.fetch_add(1, Ordering::Relaxed);
+            // Decrements the waiting count even if the caller gives up early.
+            struct Waiting<'a>(&'a AtomicUsize);
+            impl Drop for Waiting<'_> {
+                fn drop(&mut self) {
+                    self.0.fetch_sub(1, Ordering::Relaxed);
+                }
+            }
+            let _waiting = Waiting(&self.waiting);
+            self.permits.clone().acquire_owned().await.unwrap()
+        };
         {
             let mut slots = self.connections.lock().unwrap_or_else(|e| e.into_inner());
             // Pop connections until we hit a non-closed connection (or there are no
             // "possibly open" connections left).
             while let Some(c) = slots.pop() {
                 if !c.is_closed() {
-                    return PooledClient {
+                    return Ok(PooledClient {
                         client: Some(c),
                         permit,
                         pool: self.connections.clone(),
-                    };
+                    });
                 }
             }
         }
 
-        PooledClient {
-            client: Some(make_client().await.unwrap()),
+        Ok(PooledClient {
+            client: Some(make_client(self.database_url.as_deref()).await?),
             permit,
             pool: self.connections.clone(),
-        }
+        })
     }
 }
 
-async fn make_client() -> anyhow::Result<tokio_postgres::Client> {
-    let db_url = std::env::var("DATABASE_URL").expect("needs DATABASE_URL");
+async fn make_client(database_url: Option<&str>) -> anyhow::Result<tokio_postgres::Client> {
+    let db_url = match database_url {
+        Some(url) => url.to_string(),
+        None => std::env::var("DATABASE_URL").expect("needs DATABASE_URL"),
+    };
     if db_url.contains("rds.amazonaws.com") {
         let mut builder = TlsConnector::builder();
         for cert in make_certificates() {
@@ -151,52 +324,8 @@ fn cert() {
     make_certificates();
 }
 
-pub async fn run_migrations(client: &DbClient) -> anyhow::Result<()> {
-    client
-        .execute(
-            "CREATE TABLE IF NOT EXISTS database_versions (
-                zero INTEGER PRIMARY KEY,
-                migration_counter INTEGER
-            );",
-            &[],
-        )
-        .await
-        .context("creating database versioning table")?;
-
-    client
-        .execute(
-            "INSERT INTO database_versions (zero, migration_counter)
-        VALUES (0, 0)
-        ON CONFLICT DO NOTHING",
-            &[],
-        )
-        .await
-        .context("inserting initial database_versions")?;
-
-    let migration_idx: i32 = client
-        .query_one("SELECT migration_counter FROM database_versions", &[])
-        .await
-        .context("getting migration counter")?
-        .get(0);
-    let migration_idx = migration_idx as usize;
-
-    for (idx, migration) in MIGRATIONS.iter().enumerate() {
-        if idx >= migration_idx {
-            client
-                .execute(*migration, &[])
-                .await
-                .with_context(|| format!("executing {}th migration", idx))?;
-            client
-                .execute(
-                    "UPDATE database_versions SET migration_counter = $1",
-                    &[&(idx as i32 + 1)],
-                )
-                .await
-                .with_context(|| format!("updating migration counter to {}", idx))?;
-        }
-    }
-
-    Ok(())
+pub async fn run_migrations(client: &mut DbClient) -> anyhow::Result<()> {
+    migrations::MigrationRunner::new(client).run().await
 }
 
 pub async fn schedule_jobs(db: &DbClient, jobs: Vec<JobSchedule>) -> anyhow::Result<()> {
@@ -224,29 +353,94 @@ pub async fn schedule_job(
 
     if let Err(_) = get_job_by_name_and_scheduled_at(&db, job_name, &when).await {
         // mean there's no job already in the db with that name and scheduled_at
-        insert_job(&db, job_name, &when, &job_metadata).await?;
+        insert_job(&db, job_name, &when, &job_metadata, false).await?;
     }
 
     Ok(())
 }
 
 pub async fn run_scheduled_jobs(ctx: &Context, db: &DbClient) -> anyhow::Result<()> {
-    let jobs = get_jobs_to_execute(&db).await.unwrap();
+    let config = JobRunnerConfig::from_env();
+    let jobs = get_jobs_to_execute(&db, &config).await.unwrap();
     tracing::trace!("jobs to execute: {:#?}", jobs);
 
-    for job in jobs.iter() {
-        update_job_executed_at(&db, &job.id).await?;
+    // Jobs beyond what can run or wait for a slot are left in the table and
+    // picked up on the next tick.
+    let capacity = config.max_concurrent_jobs + config.max_queue_size;
+    if jobs.len() > capacity {
+        tracing::warn!(
+            "job queue is full, deferring {} job(s) to the next tick",
+            jobs.len() - capacity
+        );
+    }
 
-        match handle_job(&ctx, &job.name, &job.metadata).await {
-            Ok(_) => {
-                tracing::trace!("job successfully executed (id={})", job.id);
-                delete_job(&db, &job.id).await?;
+    let runs = jobs.iter().take(capacity).map(|job| {
+        // The span covers the whole lifecycle of the job, including its DB
+        // bookkeeping, so every log line emitted while it runs carries the
+        // job name and id.
+        let span = tracing::info_span!("job", name = %job.name, id = %job.id);
+        let config = &config;
+        async move {
+            if ctx.shutdown.is_shutting_down() {
+                tracing::info!("shutting down, leaving job for later");
+                return Ok(());
             }
-            Err(e) => {
-                tracing::error!("job failed on execution (id={:?}, error={:?})", job.id, e);
-                update_job_error_message(&db, &job.id, &e.to_string()).await?;
+            run_job(ctx, db, job, config).await
+        }
+        .instrument(span)
+    });
+    run_bounded(runs, config.max_concurrent_jobs)
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Runs `tasks` concurrently, with at most `max_concurrent` of them in
+/// progress at any time.
+async fn run_bounded<F, T>(tasks: impl IntoIterator<Item = F>, max_concurrent: usize) -> Vec<T>
+where
+    F: std::future::Future<Output = T>,
+{
+    let semaphore = Semaphore::new(max_concurrent.max(1));
+    futures::future::join_all(tasks.into_iter().map(|task| async {
+        let _permit = semaphore
+            .acquire()
+            .await
+            .expect("semaphore is never closed");
+        task.await
+    }))
+    .await
+}
+
+async fn run_job(
+    ctx: &Context,
+    db: &DbClient,
+    job: &Job,
+    config: &JobRunnerConfig,
+) -> anyhow::Result<()> {
+    let _active = ctx.shutdown.job_started();
+    update_job_executed_at(&db, &job.id, None).await?;
+
+    tracing::info!("job started");
+    let start = Instant::now();
+    let default_timeout = Duration::from_secs(config.timeout_seconds);
+    match handle_job(&ctx, &job.name, &job.metadata, default_timeout).await {
+        Ok(result) => {
+            let elapsed = start.elapsed();
+            tracing::info!(duration_ms = elapsed.as_millis() as u64, "job completed");
+            // Jobs with a result are kept so the result can be looked at
+            // later; they aren't picked up again.
+            match result {
+                Some(result) => update_job_executed_at(&db, &job.id, Some(&result)).await?,
+                None => delete_job(&db, &job.id).await?,
             }
         }
+        Err(e) => {
+            tracing::error!(error = %e, "job failed");
+            let retry_at =
+                Utc::now() + retry_delay(config, job.failure_count + 1, &mut rand::thread_rng());
+            update_job_error_message(&db, &job.id, &e.to_string(), &retry_at).await?;
+        }
     }
 
     Ok(())
@@ -257,10 +451,12 @@ async fn handle_job(
     ctx: &Context,
     name: &String,
     metadata: &serde_json::Value,
-) -> anyhow::Result<()> {
+    default_timeout: Duration,
+) -> anyhow::Result<Option<serde_json::Value>> {
     for job in jobs() {
         if &job.name() == &name {
-            return job.run(ctx, metadata).await;
+            let timeout = job.timeout().unwrap_or(default_timeout);
+            return run_with_timeout(job.run_with_result(ctx, metadata), timeout).await;
         }
     }
     tracing::trace!(
@@ -269,74 +465,72 @@ async fn handle_job(
         metadata
     );
 
-    Ok(())
+    Ok(None)
 }
 
-// Important notes when adding migrations:
-// - Each DB change is an element in this array and must be a single SQL instruction
-// - The total # of items in this array must be equal to the value of `database_versions.migration_counter`
-static MIGRATIONS: &[&str] = &[
-    "
-CREATE TABLE notifications (
-    notification_id BIGSERIAL PRIMARY KEY,
-    user_id BIGINT,
-    origin_url TEXT NOT NULL,
-    origin_html TEXT,
-    time TIMESTAMP WITH TIME ZONE
-);
-",
-    "
-CREATE TABLE users (
-    user_id BIGINT PRIMARY KEY,
-    username TEXT NOT NULL
-);
-",
-    "ALTER TABLE notifications ADD COLUMN short_description TEXT;",
-    "ALTER TABLE notifications ADD COLUMN team_name TEXT;",
-    "ALTER TABLE notifications ADD COLUMN idx INTEGER;",
-    "ALTER TABLE notifications ADD COLUMN metadata TEXT;",
-    "
-CREATE TABLE rustc_commits (
-    sha TEXT PRIMARY KEY,
-    parent_sha TEXT NOT NULL,
-    time TIMESTAMP WITH TIME ZONE
-);
-",
-    "ALTER TABLE rustc_commits ADD COLUMN pr INTEGER;",
-    "
-CREATE TABLE issue_data (
-    repo TEXT,
-    issue_number INTEGER,
-    key TEXT,
-    data JSONB,
-    PRIMARY KEY (repo, issue_number, key)
-);
-",
-    "
-CREATE TABLE jobs (
-    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
-    name TEXT NOT NULL,
-    scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL,
-    metadata JSONB,
-    executed_at TIMESTAMP WITH TIME ZONE,
-    error_message TEXT
-);
-",
-    "
-CREATE UNIQUE INDEX jobs_name_scheduled_at_unique_index
-    ON jobs (
-        name, scheduled_at
-    );
-",
-    "
-CREATE table review_prefs (
-    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
-    user_id BIGINT REFERENCES users(user_id),
-    assigned_prs INT[] NOT NULL DEFAULT array[]::INT[]
-);",
-    "
-CREATE EXTENSION IF NOT EXISTS intarray;",
-    "
-CREATE UNIQUE INDEX IF NOT EXISTS review_prefs_user_id ON review_prefs(user_id);
- ",
-];
+// Runs a job to completion, failing it if it takes longer than `timeout` so a
+// stuck job can't hold up the rest of the queue.
+async fn run_with_timeout<T>(
+    job: impl std::future::Future<Output = anyhow::Result<T>>,
+    timeout: Duration,
+) -> anyhow::Result<T> {
+    match tokio::time::timeout(timeout, job).await {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!("job timed out after {timeout:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn job_exceeding_timeout_fails() {
+        let job = async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        };
+        let err = run_with_timeout(job, Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "job timed out after 10ms");
+
+        let quick = async { Ok(()) };
+        assert!(run_with_timeout(quick, Duration::from_secs(1))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn concurrent_jobs_are_bounded() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let tasks = (0..10).map(|_| async {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert_eq!(run_bounded(tasks, 3).await.len(), 10);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn failed_job_does_not_cancel_others() {
+        let finished = AtomicUsize::new(0);
+        let tasks = (0..5).map(|i| {
+            let finished = &finished;
+            async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                finished.fetch_add(1, Ordering::SeqCst);
+                if i == 0 {
+                    anyhow::bail!("job {i} failed");
+                }
+                Ok(())
+            }
+        });
+        let results = run_bounded(tasks, 2).await;
+        assert_eq!(finished.load(Ordering::SeqCst), 5);
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+//! The `command_audit_log` table records every command dispatched to a
+//! handler, who invoked it, and how it went.
+use super::client::DbClient;
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub repo: String,
+    pub issue_number: Option<i64>,
+    /// The command as written in the comment, e.g. `@rustbot label +T-lang`.
+    pub command: String,
+    pub invoked_by: String,
+    pub invoked_at: DateTime<Utc>,
+    /// `ok` or `error` once the handler finished, `None` while it is running
+    /// (or if it never finished).
+    pub result: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Records that `invoked_by` ran `command`, returning the new entry's id so
+/// that the outcome can be filled in with [`record_command_result`].
+pub async fn record_command_invocation(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: Option<u64>,
+    command: &str,
+    invoked_by: &str,
+) -> Result<i64> {
+    let issue_number = issue_number.map(|n| n as i64);
+    let rows = db
+        .query(
+            "INSERT INTO command_audit_log (repo, issue_number, command, invoked_by, invoked_at)
+                VALUES ($1, $2, $3, $4, now())
+                RETURNING id",
+            &[&repo, &issue_number, &command, &invoked_by],
+        )
+        .await
+        .context("Inserting command audit log entry")?;
+    let row = rows
+        .first()
+        .context("command audit log insert returned no id")?;
+    Ok(row.get(0))
+}
+
+/// Stores the outcome of the invocation previously recorded as `id`.
+pub async fn record_command_result(
+    db: &impl DbClient,
+    id: i64,
+    result: &anyhow::Result<()>,
+) -> Result<()> {
+    let (outcome, error) = match result {
+        Ok(()) => ("ok", None),
+        Err(e) => ("error", Some(format!("{e:?}"))),
+    };
+    db.execute(
+        "UPDATE command_audit_log SET result = $2, error = $3 WHERE id = $1",
+        &[&id, &outcome, &error],
+    )
+    .await
+    .context("Updating command audit log entry")?;
+    Ok(())
+}
+
+/// Returns the `limit` most recent commands run on the given issue, newest
+/// first.
+pub async fn get_audit_log_for_issue(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    limit: i64,
+) -> Result<Vec<AuditLogEntry>> {
+    let rows = db
+        .query(
+            "SELECT id, repo, issue_number, command, invoked_by, invoked_at, result, error
+                FROM command_audit_log
+                WHERE repo = $1 AND issue_number = $2
+                ORDER BY invoked_at DESC, id DESC
+                LIMIT $3",
+            &[&repo, &(issue_number as i64), &limit],
+        )
+        .await
+        .context("Getting command audit log")?;
+    Ok(rows
+        .into_iter()
+        .map(|row| AuditLogEntry {
+            id: row.get(0),
+            repo: row.get(1),
+            issue_number: row.get(2),
+            command: row.get(3),
+            invoked_by: row.get(4),
+            invoked_at: row.get(5),
+            result: row.get(6),
+            error: row.get(7),
+        })
+        .collect())
+}
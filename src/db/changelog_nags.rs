@@ -0,0 +1,56 @@
+//! The `changelog_nags` table remembers which PRs were asked to update the
+//! changelog, so that the request is only made once and can be followed up
+//! when the changelog is updated.
+use super::client::DbClient;
+use anyhow::{Context as _, Result};
+
+pub async fn record_changelog_nag(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    comment_id: u64,
+) -> Result<()> {
+    db.execute(
+        "INSERT INTO changelog_nags (issue_number, repo, comment_id) VALUES ($1, $2, $3)
+            ON CONFLICT (repo, issue_number) DO UPDATE SET comment_id = EXCLUDED.comment_id",
+        &[&(issue_number as i64), &repo, &(comment_id as i64)],
+    )
+    .await
+    .context("Recording changelog nag")?;
+    Ok(())
+}
+
+/// Returns the id of the comment asking the given PR to update the changelog,
+/// if one was posted.
+pub async fn get_changelog_nag(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+) -> Result<Option<u64>> {
+    let rows = db
+        .query(
+            "SELECT comment_id FROM changelog_nags WHERE repo = $1 AND issue_number = $2",
+            &[&repo, &(issue_number as i64)],
+        )
+        .await
+        .context("Selecting changelog nag")?;
+    Ok(rows.first().map(|row| row.get::<_, i64>(0) as u64))
+}
+
+/// Forgets the changelog nag posted on the given PR, returning the id of its
+/// comment if there was one.
+pub async fn take_changelog_nag(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+) -> Result<Option<u64>> {
+    let rows = db
+        .query(
+            "DELETE FROM changelog_nags WHERE repo = $1 AND issue_number = $2
+                RETURNING comment_id",
+            &[&repo, &(issue_number as i64)],
+        )
+        .await
+        .context("Removing changelog nag")?;
+    Ok(rows.first().map(|row| row.get::<_, i64>(0) as u64))
+}
@@ -0,0 +1,35 @@
+//! An abstraction over the subset of the Postgres client API used by the
+//! query helpers in this module, so that they take pooled connections and
+//! plain clients (as used by the integration tests) alike.
+use async_trait::async_trait;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Error, Row};
+
+#[async_trait]
+pub trait DbClient: Sync {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error>;
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error>;
+}
+
+#[async_trait]
+impl DbClient for tokio_postgres::Client {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        tokio_postgres::Client::execute(self, query, params).await
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        tokio_postgres::Client::query(self, query, params).await
+    }
+}
+
+#[async_trait]
+impl<T: DbClient + ?Sized> DbClient for &T {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        (**self).execute(query, params).await
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        (**self).query(query, params).await
+    }
+}
@@ -0,0 +1,38 @@
+//! The `conflict_notifications` table remembers which PRs were told about a
+//! merge conflict, so that they can be told once it is resolved.
+use super::client::DbClient;
+use anyhow::{Context as _, Result};
+
+pub async fn record_conflict_notification(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    comment_id: u64,
+) -> Result<()> {
+    db.execute(
+        "INSERT INTO conflict_notifications (issue_number, repo, comment_id) VALUES ($1, $2, $3)
+            ON CONFLICT (repo, issue_number) DO UPDATE SET comment_id = EXCLUDED.comment_id",
+        &[&(issue_number as i64), &repo, &(comment_id as i64)],
+    )
+    .await
+    .context("Recording conflict notification")?;
+    Ok(())
+}
+
+/// Forgets the conflict notification posted on the given PR, returning the
+/// id of its comment if there was one.
+pub async fn take_conflict_notification(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+) -> Result<Option<u64>> {
+    let rows = db
+        .query(
+            "DELETE FROM conflict_notifications WHERE repo = $1 AND issue_number = $2
+                RETURNING comment_id",
+            &[&repo, &(issue_number as i64)],
+        )
+        .await
+        .context("Removing conflict notification")?;
+    Ok(rows.first().map(|row| row.get::<_, i64>(0) as u64))
+}
@@ -0,0 +1,152 @@
+//! Encryption of job metadata that shouldn't be stored in plain text, such
+//! as the numbers of private security issues.
+//!
+//! Metadata is encrypted with AES-256-GCM under a key derived with
+//! HKDF-SHA256 from the `JOB_ENCRYPTION_KEY` environment variable, and
+//! stored as
+//!
+//! ```json
+//! { "encrypted": true, "nonce": "<base64>", "ciphertext": "<base64>" }
+//! ```
+//!
+//! where the ciphertext has the authentication tag appended.
+use anyhow::{Context as _, Result};
+use openssl::base64;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const HKDF_SALT: &[u8] = b"triagebot job metadata";
+
+/// Keys derived from `JOB_ENCRYPTION_KEY`.
+pub struct JobKeys {
+    encryption: [u8; 32],
+    hashing: [u8; 32],
+}
+
+impl JobKeys {
+    pub fn from_env() -> Result<JobKeys> {
+        let secret = std::env::var("JOB_ENCRYPTION_KEY")
+            .context("JOB_ENCRYPTION_KEY must be set to encrypt job metadata")?;
+        JobKeys::derive(secret.as_bytes())
+    }
+
+    fn derive(secret: &[u8]) -> Result<JobKeys> {
+        Ok(JobKeys {
+            encryption: hkdf_sha256(HKDF_SALT, secret, b"encryption")?,
+            hashing: hkdf_sha256(HKDF_SALT, secret, b"hashing")?,
+        })
+    }
+
+    /// Wraps `metadata` in an encrypted envelope.
+    pub fn encrypt(&self, metadata: &serde_json::Value) -> Result<serde_json::Value> {
+        let mut nonce = [0; NONCE_LEN];
+        openssl::rand::rand_bytes(&mut nonce)?;
+        let mut tag = [0; TAG_LEN];
+        let mut ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.encryption,
+            Some(&nonce[..]),
+            &[],
+            metadata.to_string().as_bytes(),
+            &mut tag,
+        )
+        .context("encrypting job metadata")?;
+        ciphertext.extend_from_slice(&tag);
+        Ok(serde_json::json!({
+            "encrypted": true,
+            "nonce": base64::encode_block(&nonce),
+            "ciphertext": base64::encode_block(&ciphertext),
+        }))
+    }
+
+    /// Opens an envelope created by [`JobKeys::encrypt`].
+    pub fn decrypt(&self, envelope: &serde_json::Value) -> Result<serde_json::Value> {
+        let field = |name: &str| -> Result<Vec<u8>> {
+            let value = envelope[name]
+                .as_str()
+                .with_context(|| format!("encrypted job metadata has no {name}"))?;
+            Ok(base64::decode_block(value)?)
+        };
+        let nonce = field("nonce")?;
+        let ciphertext = field("ciphertext")?;
+        if ciphertext.len() < TAG_LEN {
+            anyhow::bail!("encrypted job metadata is too short");
+        }
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+        let plaintext = decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &self.encryption,
+            Some(&nonce[..]),
+            &[],
+            ciphertext,
+            tag,
+        )
+        .context("decrypting job metadata")?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// A keyed hash of the plain text metadata, so that encrypted jobs are
+    /// still deduplicated without the hash revealing the metadata.
+    pub fn metadata_hash(&self, metadata: &serde_json::Value) -> Result<String> {
+        Ok(hex::encode(hmac_sha256(
+            &self.hashing,
+            metadata.to_string().as_bytes(),
+        )?))
+    }
+}
+
+/// Whether `metadata` is an envelope created by [`JobKeys::encrypt`].
+pub fn is_encrypted(metadata: &serde_json::Value) -> bool {
+    metadata["encrypted"] == serde_json::Value::Bool(true)
+}
+
+/// HKDF-SHA256 (RFC 5869) producing a single block of output.
+fn hkdf_sha256(salt: &[u8], secret: &[u8], info: &[u8]) -> Result<[u8; 32]> {
+    let prk = hmac_sha256(salt, secret)?;
+    let mut block_input = info.to_vec();
+    block_input.push(1);
+    hmac_sha256(&prk, &block_input)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<[u8; 32]> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    let mut out = [0; 32];
+    signer.sign(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_round_trips() {
+        let keys = JobKeys::derive(b"secret").unwrap();
+        let metadata = serde_json::json!({ "repo": "rust-lang/rust", "number": 1 });
+        let envelope = keys.encrypt(&metadata).unwrap();
+        assert!(is_encrypted(&envelope));
+        assert!(!envelope.to_string().contains("rust-lang"));
+        assert_eq!(keys.decrypt(&envelope).unwrap(), metadata);
+
+        let other = JobKeys::derive(b"other secret").unwrap();
+        assert!(other.decrypt(&envelope).is_err());
+        assert!(!is_encrypted(&metadata));
+    }
+
+    #[test]
+    fn hkdf_matches_rfc_5869() {
+        // Test case 1 from RFC 5869, of which we only use the first block.
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        assert_eq!(
+            hex::encode(hkdf_sha256(&salt, &[0x0b; 22], &info).unwrap()),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 //! The `jobs` table provides a way to have scheduled jobs
+use super::client::DbClient;
+use super::job_encryption::{is_encrypted, JobKeys};
+use super::JobRunnerConfig;
 use anyhow::{Context as _, Result};
 use chrono::{DateTime, Utc};
 use cron::Schedule;
 use serde::{Deserialize, Serialize};
-use tokio_postgres::Client as DbClient;
 use uuid::Uuid;
 
 pub struct JobSchedule {
@@ -20,28 +22,80 @@ pub struct Job {
     pub metadata: serde_json::Value,
     pub executed_at: Option<DateTime<Utc>>,
     pub error_message: Option<String>,
+    /// What a successful run returned, see `Job::run_with_result`.
+    pub result: Option<serde_json::Value>,
+    /// How many times the job has failed.
+    pub failure_count: i32,
 }
 
+/// Queues a job.
+///
+/// With `encrypt_metadata`, the metadata is stored encrypted (see
+/// [`super::job_encryption`]) and decrypted again by [`get_jobs_to_execute`].
 pub async fn insert_job(
-    db: &DbClient,
+    db: &impl DbClient,
     name: &str,
     scheduled_at: &DateTime<Utc>,
     metadata: &serde_json::Value,
+    encrypt_metadata: bool,
 ) -> Result<()> {
     tracing::trace!("insert_job(name={})", name);
 
-    db.execute(
-        "INSERT INTO jobs (name, scheduled_at, metadata) VALUES ($1, $2, $3) 
-            ON CONFLICT (name, scheduled_at) DO UPDATE SET metadata = EXCLUDED.metadata",
-        &[&name, &scheduled_at, &metadata],
-    )
-    .await
-    .context("Inserting job")?;
+    let (stored, hash) = stored_metadata(metadata, encrypt_metadata)?;
+    db.execute(INSERT_JOB, &[&name, &scheduled_at, &stored, &hash])
+        .await
+        .context("Inserting job")?;
 
     Ok(())
 }
 
-pub async fn delete_job(db: &DbClient, id: &Uuid) -> Result<()> {
+/// Like [`insert_job`], but returns the job's id so that it can be cancelled
+/// later with [`delete_job`].
+pub async fn insert_job_returning_id(
+    db: &impl DbClient,
+    name: &str,
+    scheduled_at: &DateTime<Utc>,
+    metadata: &serde_json::Value,
+    encrypt_metadata: bool,
+) -> Result<Uuid> {
+    tracing::trace!("insert_job_returning_id(name={})", name);
+
+    let (stored, hash) = stored_metadata(metadata, encrypt_metadata)?;
+    let rows = db
+        .query(
+            &format!("{INSERT_JOB} RETURNING id"),
+            &[&name, &scheduled_at, &stored, &hash],
+        )
+        .await
+        .context("Inserting job")?;
+    let row = rows.first().context("job insert returned no id")?;
+    Ok(row.get(0))
+}
+
+const INSERT_JOB: &str =
+    "INSERT INTO jobs (name, scheduled_at, metadata, metadata_hash) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (name, scheduled_at, metadata_hash) DO UPDATE SET metadata = EXCLUDED.metadata";
+
+/// The metadata as it is stored in the `jobs` table, and its hash.
+fn stored_metadata(
+    metadata: &serde_json::Value,
+    encrypt_metadata: bool,
+) -> Result<(serde_json::Value, String)> {
+    if encrypt_metadata {
+        let keys = JobKeys::from_env()?;
+        Ok((keys.encrypt(metadata)?, keys.metadata_hash(metadata)?))
+    } else {
+        Ok((metadata.clone(), metadata_hash(metadata)))
+    }
+}
+
+/// Part of a job's identity, so that jobs with the same name and time but
+/// different metadata (e.g. for different issues) don't replace each other.
+fn metadata_hash(metadata: &serde_json::Value) -> String {
+    hex::encode(openssl::sha::sha256(metadata.to_string().as_bytes()))
+}
+
+pub async fn delete_job(db: &impl DbClient, id: &Uuid) -> Result<()> {
     tracing::trace!("delete_job(id={})", id);
 
     db.execute("DELETE FROM jobs WHERE id = $1", &[&id])
@@ -51,12 +105,19 @@ pub async fn delete_job(db: &DbClient, id: &Uuid) -> Result<()> {
     Ok(())
 }
 
-pub async fn update_job_error_message(db: &DbClient, id: &Uuid, message: &String) -> Result<()> {
+/// Records a failed run, to be retried at `retry_at`.
+pub async fn update_job_error_message(
+    db: &impl DbClient,
+    id: &Uuid,
+    message: &String,
+    retry_at: &DateTime<Utc>,
+) -> Result<()> {
     tracing::trace!("update_job_error_message(id={})", id);
 
     db.execute(
-        "UPDATE jobs SET error_message = $2 WHERE id = $1",
-        &[&id, &message],
+        "UPDATE jobs SET error_message = $2, failure_count = failure_count + 1, retry_at = $3 \
+         WHERE id = $1",
+        &[&id, &message, &retry_at],
     )
     .await
     .context("Updating job error message")?;
@@ -64,18 +125,27 @@ pub async fn update_job_error_message(db: &DbClient, id: &Uuid, message: &String
     Ok(())
 }
 
-pub async fn update_job_executed_at(db: &DbClient, id: &Uuid) -> Result<()> {
+/// Sets the job's `executed_at` to now, along with the result of a
+/// successful run if there is one.
+pub async fn update_job_executed_at(
+    db: &impl DbClient,
+    id: &Uuid,
+    result: Option<&serde_json::Value>,
+) -> Result<()> {
     tracing::trace!("update_job_executed_at(id={})", id);
 
-    db.execute("UPDATE jobs SET executed_at = now() WHERE id = $1", &[&id])
-        .await
-        .context("Updating job executed at")?;
+    db.execute(
+        "UPDATE jobs SET executed_at = now(), result = $2 WHERE id = $1",
+        &[&id, &result],
+    )
+    .await
+    .context("Updating job executed at")?;
 
     Ok(())
 }
 
 pub async fn get_job_by_name_and_scheduled_at(
-    db: &DbClient,
+    db: &tokio_postgres::Client,
     name: &str,
     scheduled_at: &DateTime<Utc>,
 ) -> Result<Job> {
@@ -96,28 +166,152 @@ pub async fn get_job_by_name_and_scheduled_at(
     deserialize_job(&job)
 }
 
+/// Returns all queued jobs with the given name, oldest first.
+pub async fn get_jobs_by_name(db: &impl DbClient, name: &str) -> Result<Vec<Job>> {
+    tracing::trace!("get_jobs_by_name(name={})", name);
+
+    let jobs = db
+        .query(
+            "SELECT * FROM jobs WHERE name = $1 ORDER BY scheduled_at",
+            &[&name],
+        )
+        .await
+        .context("Select jobs by name")?;
+
+    jobs.iter().map(deserialize_job).collect()
+}
+
 // Selects all jobs with:
 //  - scheduled_at in the past
 //  - error_message is null or executed_at is at least 60 minutes ago (intended to make repeat executions rare enough)
-pub async fn get_jobs_to_execute(db: &DbClient) -> Result<Vec<Job>> {
+/// Returns up to `config.batch_size` due jobs, oldest first.
+///
+/// Failed jobs are included again once their `retry_at` (see
+/// [`retry_delay`]) has passed, until they have been retried
+/// `config.max_retries` times.
+pub async fn get_jobs_to_execute(db: &impl DbClient, config: &JobRunnerConfig) -> Result<Vec<Job>> {
     let jobs = db
         .query(
             "
-        SELECT * FROM jobs WHERE scheduled_at <= now() AND (error_message IS NULL OR executed_at <= now() - INTERVAL '60 minutes')",
-            &[],
+        SELECT * FROM jobs WHERE scheduled_at <= now() AND result IS NULL AND (error_message IS NULL OR (COALESCE(retry_at, executed_at + make_interval(mins => $2)) <= now() AND failure_count <= $3))
+        ORDER BY scheduled_at ASC LIMIT $1",
+            &[&config.batch_size, &config.retry_minutes, &config.max_retries],
         )
         .await
         .context("Getting jobs data")?;
 
     let mut data = Vec::with_capacity(jobs.len());
     for job in jobs {
-        let serialized_job = deserialize_job(&job);
-        data.push(serialized_job.unwrap());
+        let mut job = deserialize_job(&job).unwrap();
+        if is_encrypted(&job.metadata) {
+            // A job that can't be decrypted (e.g. because the key changed)
+            // isn't run with the wrong metadata. It fails like any other job,
+            // so that it's retried with backoff and given up on after
+            // `config.max_retries` rather than selected again every time.
+            match JobKeys::from_env().and_then(|keys| keys.decrypt(&job.metadata)) {
+                Ok(metadata) => job.metadata = metadata,
+                Err(e) => {
+                    tracing::error!("skipping job {} ({}): {e:?}", job.id, job.name);
+                    let retry_at = Utc::now()
+                        + retry_delay(config, job.failure_count + 1, &mut rand::thread_rng());
+                    update_job_error_message(
+                        db,
+                        &job.id,
+                        &format!("failed to decrypt metadata: {e}"),
+                        &retry_at,
+                    )
+                    .await?;
+                    continue;
+                }
+            }
+        }
+        data.push(job);
     }
 
     Ok(data)
 }
 
+/// The longest a failed job waits before being retried.
+const MAX_RETRY_DELAY_MINUTES: i64 = 24 * 60;
+
+/// How long to wait before retrying a job that has failed `failure_count`
+/// times.
+///
+/// The delay starts at `config.retry_minutes` and doubles with every
+/// failure. Only half of it is fixed and the rest is random, so that jobs
+/// which failed together (e.g. during a GitHub outage) don't all retry at
+/// the same moment.
+pub fn retry_delay(
+    config: &JobRunnerConfig,
+    failure_count: i32,
+    rng: &mut impl rand::Rng,
+) -> chrono::Duration {
+    let doublings = failure_count.saturating_sub(1).clamp(0, 16) as u32;
+    let backoff_secs =
+        (i64::from(config.retry_minutes) << doublings).min(MAX_RETRY_DELAY_MINUTES) * 60;
+    let fixed = backoff_secs / 2;
+    chrono::Duration::seconds(fixed + rng.gen_range(0..=backoff_secs - fixed))
+}
+
+/// Whether a job already posted the comment identified by `key`.
+///
+/// Jobs that post comments check this first and call
+/// [`record_posted_comment`] afterwards, so that a job re-run after a crash
+/// between posting and being deleted doesn't post the same comment twice.
+pub async fn comment_already_posted(db: &impl DbClient, key: &str) -> Result<bool> {
+    let rows = db
+        .query(
+            "SELECT 1 FROM posted_comments WHERE idempotency_key = $1",
+            &[&key],
+        )
+        .await
+        .context("Checking posted comments")?;
+    Ok(!rows.is_empty())
+}
+
+pub async fn record_posted_comment(db: &impl DbClient, key: &str) -> Result<()> {
+    db.execute(
+        "INSERT INTO posted_comments (idempotency_key, posted_at) VALUES ($1, now())
+            ON CONFLICT (idempotency_key) DO NOTHING",
+        &[&key],
+    )
+    .await
+    .context("Recording posted comment")?;
+    Ok(())
+}
+
+/// A summary of the job queue, used to spot a stalled scheduler.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SchedulerHealth {
+    /// Jobs whose scheduled time has passed but haven't been deleted yet.
+    pub due_jobs: usize,
+    /// The scheduled time of the oldest due job. If this keeps getting
+    /// older, jobs aren't being run.
+    pub oldest_due: Option<DateTime<Utc>>,
+    /// Jobs that failed more than `max_retries` times and won't be run
+    /// again.
+    pub failed_jobs: usize,
+}
+
+pub async fn get_scheduler_health(db: &impl DbClient, max_retries: i32) -> Result<SchedulerHealth> {
+    let rows = db
+        .query(
+            "SELECT count(*) FILTER (WHERE scheduled_at <= now()), \
+                    min(scheduled_at) FILTER (WHERE scheduled_at <= now()), \
+                    count(*) FILTER (WHERE failure_count > $1) \
+             FROM jobs WHERE result IS NULL",
+            &[&max_retries],
+        )
+        .await
+        .context("Getting scheduler health")?;
+    let row = rows.first().context("aggregate query returned no row")?;
+    Ok(SchedulerHealth {
+        due_jobs: row.get::<_, i64>(0) as usize,
+        oldest_due: row.get(1),
+        failed_jobs: row.get::<_, i64>(2) as usize,
+    })
+}
+
 fn deserialize_job(row: &tokio_postgres::row::Row) -> Result<Job> {
     let id: Uuid = row.try_get(0)?;
     let name: String = row.try_get(1)?;
@@ -125,6 +319,8 @@ fn deserialize_job(row: &tokio_postgres::row::Row) -> Result<Job> {
     let metadata: serde_json::Value = row.try_get(3)?;
     let executed_at: Option<DateTime<Utc>> = row.try_get(4)?;
     let error_message: Option<String> = row.try_get(5)?;
+    let result: Option<serde_json::Value> = row.try_get("result")?;
+    let failure_count: i32 = row.try_get("failure_count")?;
 
     Ok(Job {
         id,
@@ -133,5 +329,74 @@ fn deserialize_job(row: &tokio_postgres::row::Row) -> Result<Job> {
         metadata,
         executed_at,
         error_message,
+        result,
+        failure_count,
     })
 }
+
+/// Due jobs that haven't been run at all by more than `overdue_by` after
+/// their scheduled time, oldest first. Jobs already passed to
+/// [`record_overdue_alert`] are left out.
+///
+/// Unlike failed jobs, these point at a job runner that is stuck or too far
+/// behind to keep up.
+pub async fn get_overdue_jobs(
+    db: &impl DbClient,
+    overdue_by: chrono::Duration,
+) -> Result<Vec<Job>> {
+    let jobs = db
+        .query(
+            "SELECT * FROM jobs WHERE result IS NULL AND executed_at IS NULL \
+             AND overdue_alerted_at IS NULL AND scheduled_at <= now() - make_interval(secs => $1) \
+             ORDER BY scheduled_at ASC",
+            &[&(overdue_by.num_seconds() as f64)],
+        )
+        .await
+        .context("Getting overdue jobs")?;
+    jobs.iter().map(deserialize_job).collect()
+}
+
+/// Records that operators were alerted about an overdue job.
+pub async fn record_overdue_alert(db: &impl DbClient, id: &Uuid) -> Result<()> {
+    db.execute(
+        "UPDATE jobs SET overdue_alerted_at = now() WHERE id = $1",
+        &[&id],
+    )
+    .await
+    .context("Recording overdue alert")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delays_back_off_with_jitter() {
+        use rand::SeedableRng;
+
+        let config = JobRunnerConfig {
+            retry_minutes: 60,
+            ..JobRunnerConfig::default()
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let minutes = |failure_count, rng: &mut _| {
+            (0..1000)
+                .map(|_| retry_delay(&config, failure_count, rng).num_minutes())
+                .collect::<Vec<_>>()
+        };
+
+        let first = minutes(1, &mut rng);
+        assert!(first.iter().all(|m| (30..=60).contains(m)));
+        // The delays cover the whole window rather than bunching up.
+        for window in [30..40, 40..50, 50..61] {
+            let in_window = first.iter().filter(|m| window.contains(m)).count();
+            assert!(in_window > 200, "{in_window} delays in {window:?}");
+        }
+
+        assert!(minutes(3, &mut rng).iter().all(|m| (120..=240).contains(m)));
+        assert!(minutes(10, &mut rng)
+            .iter()
+            .all(|m| (12 * 60..=24 * 60).contains(m)));
+    }
+}
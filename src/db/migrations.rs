@@ -0,0 +1,420 @@
+//! Tracks and applies the database schema migrations.
+//!
+//! Every migration in [`MIGRATIONS`] is numbered by its position, starting at 1.
+//! Applied versions are recorded in the `schema_migrations` table, and at
+//! startup [`MigrationRunner`] runs all migrations newer than the highest
+//! applied version, each in its own transaction.
+//!
+//! Databases created before `schema_migrations` existed tracked their state in
+//! the `database_versions` counter; it is used to backfill the applied versions
+//! the first time the runner starts.
+
+use anyhow::Context as _;
+use tokio_postgres::Client as DbClient;
+
+pub struct MigrationRunner<'a> {
+    client: &'a mut DbClient,
+    migrations: &'static [&'static str],
+}
+
+impl<'a> MigrationRunner<'a> {
+    pub fn new(client: &'a mut DbClient) -> MigrationRunner<'a> {
+        MigrationRunner {
+            client,
+            migrations: MIGRATIONS,
+        }
+    }
+
+    /// The number of migrations known to this build.
+    pub fn code_version(&self) -> usize {
+        self.migrations.len()
+    }
+
+    /// The highest migration version applied to the database.
+    pub async fn applied_version(&self) -> anyhow::Result<usize> {
+        let version: Option<i32> = self
+            .client
+            .query_one("SELECT MAX(version) FROM schema_migrations", &[])
+            .await
+            .context("getting applied migration version")?
+            .get(0);
+        Ok(version.unwrap_or(0) as usize)
+    }
+
+    /// Applies all pending migrations in order, stopping at the first failure.
+    pub async fn run(self) -> anyhow::Result<()> {
+        self.client
+            .execute(
+                "CREATE TABLE IF NOT EXISTS schema_migrations (
+                    version INTEGER PRIMARY KEY,
+                    applied_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+                );",
+                &[],
+            )
+            .await
+            .context("creating schema_migrations table")?;
+        self.backfill_from_database_versions().await?;
+
+        let applied = self.applied_version().await?;
+        if applied > self.code_version() {
+            anyhow::bail!(
+                "database is at migration {applied}, but this build only knows {} migrations",
+                self.code_version()
+            );
+        }
+
+        for (version, migration) in pending(self.migrations, applied) {
+            let tx = self
+                .client
+                .transaction()
+                .await
+                .context("starting migration transaction")?;
+            tx.execute(*migration, &[])
+                .await
+                .with_context(|| format!("executing migration {version}"))?;
+            tx.execute(
+                "INSERT INTO schema_migrations (version) VALUES ($1)",
+                &[&(version as i32)],
+            )
+            .await
+            .with_context(|| format!("recording migration {version}"))?;
+            tx.commit()
+                .await
+                .with_context(|| format!("committing migration {version}"))?;
+        }
+
+        Ok(())
+    }
+
+    async fn backfill_from_database_versions(&self) -> anyhow::Result<()> {
+        if self.applied_version().await? > 0 {
+            return Ok(());
+        }
+        let has_legacy: bool = self
+            .client
+            .query_one("SELECT to_regclass('database_versions') IS NOT NULL", &[])
+            .await
+            .context("checking for database_versions")?
+            .get(0);
+        if !has_legacy {
+            return Ok(());
+        }
+        let counter: Option<i32> = self
+            .client
+            .query_opt("SELECT migration_counter FROM database_versions", &[])
+            .await
+            .context("getting migration counter")?
+            .and_then(|row| row.get(0));
+        let counter = counter.unwrap_or(0);
+        if counter > 0 {
+            self.client
+                .execute(
+                    "INSERT INTO schema_migrations (version)
+                     SELECT generate_series(1, $1) ON CONFLICT DO NOTHING",
+                    &[&counter],
+                )
+                .await
+                .context("backfilling schema_migrations")?;
+        }
+        Ok(())
+    }
+}
+
+/// The migrations after `applied`, paired with their version.
+fn pending(
+    migrations: &[&'static str],
+    applied: usize,
+) -> impl Iterator<Item = (usize, &&'static str)> {
+    migrations
+        .iter()
+        .enumerate()
+        .map(|(idx, migration)| (idx + 1, migration))
+        .skip(applied)
+}
+
+#[test]
+fn pending_migrations_start_after_applied_version() {
+    let migrations = &["A", "B", "C"];
+    let versions: Vec<_> = pending(migrations, 1).map(|(v, m)| (v, *m)).collect();
+    assert_eq!(versions, vec![(2, "B"), (3, "C")]);
+    assert_eq!(pending(migrations, 3).count(), 0);
+}
+
+// Important notes when adding migrations:
+// - Each DB change is an element in this array and must be a single SQL instruction
+// - Migrations are only ever appended; the version of a migration is its position in this array
+//   (starting at 1), and it is recorded in `schema_migrations` once applied
+static MIGRATIONS: &[&str] = &[
+    "
+CREATE TABLE notifications (
+    notification_id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT,
+    origin_url TEXT NOT NULL,
+    origin_html TEXT,
+    time TIMESTAMP WITH TIME ZONE
+);
+",
+    "
+CREATE TABLE users (
+    user_id BIGINT PRIMARY KEY,
+    username TEXT NOT NULL
+);
+",
+    "ALTER TABLE notifications ADD COLUMN short_description TEXT;",
+    "ALTER TABLE notifications ADD COLUMN team_name TEXT;",
+    "ALTER TABLE notifications ADD COLUMN idx INTEGER;",
+    "ALTER TABLE notifications ADD COLUMN metadata TEXT;",
+    "
+CREATE TABLE rustc_commits (
+    sha TEXT PRIMARY KEY,
+    parent_sha TEXT NOT NULL,
+    time TIMESTAMP WITH TIME ZONE
+);
+",
+    "ALTER TABLE rustc_commits ADD COLUMN pr INTEGER;",
+    "
+CREATE TABLE issue_data (
+    repo TEXT,
+    issue_number INTEGER,
+    key TEXT,
+    data JSONB,
+    PRIMARY KEY (repo, issue_number, key)
+);
+",
+    "
+CREATE TABLE jobs (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    name TEXT NOT NULL,
+    scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    metadata JSONB,
+    executed_at TIMESTAMP WITH TIME ZONE,
+    error_message TEXT
+);
+",
+    "
+CREATE UNIQUE INDEX jobs_name_scheduled_at_unique_index
+    ON jobs (
+        name, scheduled_at
+    );
+",
+    "
+CREATE table review_prefs (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id BIGINT REFERENCES users(user_id),
+    assigned_prs INT[] NOT NULL DEFAULT array[]::INT[]
+);",
+    "
+CREATE EXTENSION IF NOT EXISTS intarray;",
+    "
+CREATE UNIQUE INDEX IF NOT EXISTS review_prefs_user_id ON review_prefs(user_id);
+ ",
+    "
+CREATE TABLE perf_requests (
+    repo TEXT NOT NULL,
+    issue_number BIGINT NOT NULL,
+    requested_by TEXT NOT NULL,
+    requested_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    result_url TEXT
+);
+",
+    "
+CREATE TABLE tracking_issues (
+    repo TEXT NOT NULL,
+    issue_number BIGINT NOT NULL,
+    feature_name TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    stabilized_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (repo, issue_number)
+);
+",
+    "
+CREATE TABLE meeting_agendas (
+    repo TEXT PRIMARY KEY,
+    bot_comment_id BIGINT,
+    last_run_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+",
+    "
+CREATE TABLE contributor_digests (
+    week_start DATE NOT NULL,
+    repo TEXT NOT NULL,
+    data JSONB NOT NULL,
+    PRIMARY KEY (repo, week_start)
+);
+",
+    "
+CREATE TABLE registered_repos (
+    feature TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    registered_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (feature, repo)
+);
+",
+    "
+CREATE TABLE command_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    repo TEXT NOT NULL,
+    issue_number BIGINT,
+    command TEXT NOT NULL,
+    invoked_by TEXT NOT NULL,
+    invoked_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    result TEXT,
+    error TEXT
+);
+",
+    "
+CREATE INDEX command_audit_log_issue_idx ON command_audit_log (repo, issue_number, invoked_at);
+",
+    "
+CREATE TABLE user_rate_limits (
+    login TEXT NOT NULL,
+    window_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (login, window_start)
+);
+",
+    "
+ALTER TABLE jobs ADD COLUMN metadata_hash TEXT NOT NULL DEFAULT '';
+",
+    "
+DROP INDEX jobs_name_scheduled_at_unique_index;
+",
+    "
+CREATE UNIQUE INDEX jobs_name_scheduled_at_metadata_hash_unique_index
+    ON jobs (
+        name, scheduled_at, metadata_hash
+    );
+",
+    "
+CREATE TABLE posted_comments (
+    idempotency_key TEXT PRIMARY KEY,
+    posted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+",
+    "
+ALTER TABLE jobs ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
+",
+    "
+CREATE TABLE conflict_notifications (
+    issue_number BIGINT NOT NULL,
+    repo TEXT NOT NULL,
+    comment_id BIGINT NOT NULL,
+    PRIMARY KEY (repo, issue_number)
+);
+",
+    "
+CREATE TABLE issue_priority_scores (
+    issue_number BIGINT NOT NULL,
+    repo TEXT NOT NULL,
+    score INT NOT NULL,
+    scored_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+",
+    "
+CREATE INDEX issue_priority_scores_repo_issue_number_index ON issue_priority_scores (repo, issue_number);
+",
+    "
+ALTER TABLE jobs ADD COLUMN result JSONB;
+",
+    "
+CREATE TABLE sla_timers (
+    issue_number BIGINT NOT NULL,
+    repo TEXT NOT NULL,
+    job_id UUID NOT NULL,
+    PRIMARY KEY (repo, issue_number)
+);
+",
+    "
+CREATE TABLE on_call_assignments (
+    team TEXT NOT NULL,
+    assignee TEXT NOT NULL,
+    week_start DATE NOT NULL,
+    week_end DATE NOT NULL,
+    PRIMARY KEY (team, week_start)
+);
+",
+    "
+CREATE TABLE reported_advisories (
+    repo TEXT NOT NULL,
+    advisory_id TEXT NOT NULL,
+    reported_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (repo, advisory_id)
+);
+",
+    "
+CREATE TABLE msrv_history (
+    repo TEXT NOT NULL,
+    version TEXT NOT NULL,
+    changed_in_pr BIGINT NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (repo, changed_in_pr, version)
+);
+",
+    "
+CREATE TABLE changelog_nags (
+    issue_number BIGINT NOT NULL,
+    repo TEXT NOT NULL,
+    comment_id BIGINT NOT NULL,
+    PRIMARY KEY (repo, issue_number)
+);
+",
+    "
+ALTER TABLE jobs ADD COLUMN retry_at TIMESTAMP WITH TIME ZONE;
+",
+    "
+ALTER TABLE jobs ADD COLUMN overdue_alerted_at TIMESTAMP WITH TIME ZONE;
+",
+    "
+CREATE TABLE toolchain_versions (
+    repo TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    version TEXT NOT NULL,
+    detected_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (repo, channel)
+);
+",
+    "
+CREATE TABLE crater_requests (
+    source_repo TEXT NOT NULL,
+    source_pr BIGINT NOT NULL,
+    crater_repo TEXT NOT NULL,
+    crater_issue BIGINT NOT NULL,
+    requested_by TEXT NOT NULL,
+    status TEXT NOT NULL,
+    PRIMARY KEY (crater_repo, crater_issue)
+);
+",
+    "
+CREATE TABLE needs_info_timers (
+    issue_number BIGINT NOT NULL,
+    repo TEXT NOT NULL,
+    job_id UUID NOT NULL,
+    PRIMARY KEY (repo, issue_number)
+);
+",
+    "
+CREATE TABLE edition_compat (
+    item TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    status TEXT NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    PRIMARY KEY (repo, item)
+);
+",
+    "
+CREATE TABLE edition_summary_comments (
+    repo TEXT PRIMARY KEY,
+    comment_id BIGINT NOT NULL
+);
+",
+    "
+CREATE TABLE rollup_prs (
+    rollup_pr BIGINT NOT NULL,
+    constituent_pr BIGINT NOT NULL,
+    repo TEXT NOT NULL,
+    PRIMARY KEY (repo, rollup_pr, constituent_pr)
+);
+",
+];
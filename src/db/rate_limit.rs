@@ -0,0 +1,62 @@
+//! The `user_rate_limits` table counts the commands each user has sent in the
+//! current minute, so that a burst of comments can't flood the bot.
+use super::client::DbClient;
+use anyhow::{Context as _, Result};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    pub max_commands_per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            max_commands_per_minute: 10,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Reads `TRIAGEBOT_MAX_COMMANDS_PER_MINUTE`, falling back to the default.
+    pub fn from_env() -> RateLimitConfig {
+        let default = RateLimitConfig::default();
+        RateLimitConfig {
+            max_commands_per_minute: std::env::var("TRIAGEBOT_MAX_COMMANDS_PER_MINUTE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_commands_per_minute),
+        }
+    }
+}
+
+/// Counts a command from `login` against the current minute.
+///
+/// Returns `false` without counting it if `login` has already sent `max`
+/// commands this minute. Windows older than two minutes are pruned.
+pub async fn check_and_increment_rate_limit(
+    db: &impl DbClient,
+    login: &str,
+    max: u32,
+) -> Result<bool> {
+    db.execute(
+        "DELETE FROM user_rate_limits WHERE window_start < now() - INTERVAL '2 minutes'",
+        &[],
+    )
+    .await
+    .context("Pruning user rate limits")?;
+
+    // When the limit has been reached the conflicting row is left alone, so
+    // no row is affected.
+    let counted = db
+        .execute(
+            "INSERT INTO user_rate_limits (login, window_start, count)
+                VALUES ($1, date_trunc('minute', now()), 1)
+                ON CONFLICT (login, window_start) DO UPDATE
+                SET count = user_rate_limits.count + 1
+                WHERE user_rate_limits.count < $2",
+            &[&login, &(max as i32)],
+        )
+        .await
+        .context("Incrementing user rate limit")?;
+    Ok(counted > 0)
+}
@@ -0,0 +1,30 @@
+//! The `registered_repos` table lists the repositories that use a feature run
+//! by a periodic job.
+//!
+//! Jobs don't receive events, so they can't tell which repositories have the
+//! feature configured. Instead the feature's handler registers the repository
+//! when it sees an event for it, and the job goes through
+//! [`registered_repos`].
+use super::client::DbClient;
+use anyhow::{Context as _, Result};
+
+pub async fn register_repo(db: &impl DbClient, feature: &str, repo: &str) -> Result<()> {
+    db.execute(
+        "INSERT INTO registered_repos (feature, repo) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        &[&feature, &repo],
+    )
+    .await
+    .context("Registering repository")?;
+    Ok(())
+}
+
+pub async fn registered_repos(db: &impl DbClient, feature: &str) -> Result<Vec<String>> {
+    let rows = db
+        .query(
+            "SELECT repo FROM registered_repos WHERE feature = $1 ORDER BY repo",
+            &[&feature],
+        )
+        .await
+        .context("Selecting registered repositories")?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
@@ -11,7 +11,8 @@ use reqwest::{Client, Request, RequestBuilder, Response, StatusCode};
 use std::collections::{HashMap, HashSet};
 use std::{
     fmt,
-    time::{Duration, SystemTime},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
 use tracing as log;
 
@@ -26,11 +27,41 @@ impl GithubClient {
         const MAX_ATTEMPTS: u32 = 2;
         log::debug!("send_req with {:?}", req);
         let req_dbg = format!("{:?}", req);
-        let req = req
+        let mut req = req
             .build()
             .with_context(|| format!("building reqwest {}", req_dbg))?;
 
-        let mut resp = self.client.execute(req.try_clone().unwrap()).await?;
+        // Requests that don't authenticate (like those made through `raw()`)
+        // aren't counted against any token.
+        let token_index = if req.headers().contains_key(AUTHORIZATION) {
+            let (index, token) = self.next_available_token().await;
+            req.headers_mut().insert(AUTHORIZATION, auth_header(&token));
+            Some(index)
+        } else {
+            None
+        };
+        if let (Some(index), true) = (token_index, is_write(req.method())) {
+            self.space_write(index).await;
+        }
+        let resp = self.client.execute(req.try_clone().unwrap()).await;
+        if token_index.is_some() {
+            let failed = match &resp {
+                Ok(resp) => {
+                    resp.status().is_server_error() || resp.status() == StatusCode::UNAUTHORIZED
+                }
+                Err(_) => true,
+            };
+            self.api_health
+                .lock()
+                .unwrap()
+                .record(failed, Instant::now());
+        }
+        let mut resp = resp?;
+        if let (Some(index), Some(snapshot)) =
+            (token_index, RateLimitSnapshot::from_headers(resp.headers()))
+        {
+            self.tokens.lock().unwrap()[index].rate_limit = Some(snapshot);
+        }
         if self.retry_rate_limit {
             if let Some(sleep) = Self::needs_retry(&resp).await {
                 resp = self.retry(req, sleep, MAX_ATTEMPTS).await?;
@@ -69,6 +100,37 @@ impl GithubClient {
         Some(Duration::from_secs(Self::calc_sleep(reset_time) + 10))
     }
 
+    /// Returns the index and value of the token with the most remaining
+    /// requests, sleeping until the soonest reset if all are exhausted.
+    async fn next_available_token(&self) -> (usize, String) {
+        let epoch_time = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs();
+        let (index, sleep) = pick_token(&self.tokens.lock().unwrap(), epoch_time);
+        if let Some(sleep) = sleep {
+            log::warn!(
+                "all GitHub tokens are rate limited, sleeping {} seconds",
+                sleep.as_secs()
+            );
+            tokio::time::sleep(sleep).await;
+        }
+        let token = self.tokens.lock().unwrap()[index].token.clone();
+        (index, token)
+    }
+
+    /// Sleeps before a write if the last response for the given token
+    /// reported that its primary rate limit is nearly exhausted.
+    async fn space_write(&self, token_index: usize) {
+        let snapshot = self.tokens.lock().unwrap()[token_index].rate_limit;
+        let epoch_time = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs();
+        if let Some(delay) = snapshot.and_then(|s| s.write_delay(epoch_time)) {
+            log::info!(
+                "rate limit low ({:?}), delaying write by {:?}",
+                snapshot,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
     fn calc_sleep(reset_time: u64) -> u64 {
         let epoch_time = SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs();
         reset_time.saturating_sub(epoch_time)
@@ -304,6 +366,11 @@ pub struct Issue {
     pub merged: bool,
     #[serde(default)]
     pub draft: bool,
+    /// Whether a pull request can be merged without conflicts.
+    ///
+    /// `None` while GitHub is still computing it, and for issues.
+    #[serde(default)]
+    pub mergeable: Option<bool>,
     /// The API URL for discussion comments.
     ///
     /// Example: `https://api.github.com/repos/octocat/Hello-World/issues/1347/comments`
@@ -323,6 +390,81 @@ pub struct Issue {
     pub head: Option<CommitBase>,
     /// Whether it is open or closed.
     pub state: IssueState,
+    /// The milestone this issue or PR is assigned to, if any.
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+}
+
+/// Builds [`Issue`]s for unit tests, starting from an open issue with an
+/// empty body and no labels.
+#[cfg(test)]
+pub(crate) struct IssueBuilder(serde_json::Value);
+
+#[cfg(test)]
+impl IssueBuilder {
+    pub(crate) fn new(repo: &str, number: u64) -> IssueBuilder {
+        IssueBuilder(serde_json::json!({
+            "number": number,
+            "body": "",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "title": "Something is broken",
+            "html_url": format!("https://github.com/{repo}/issues/{number}"),
+            "user": { "login": "author", "id": 1 },
+            "labels": [],
+            "assignees": [],
+            "comments_url": format!("https://api.github.com/repos/{repo}/issues/{number}/comments"),
+            "state": "open",
+        }))
+    }
+
+    pub(crate) fn pr(mut self) -> IssueBuilder {
+        let html_url = self.0["html_url"]
+            .as_str()
+            .unwrap()
+            .replace("/issues/", "/pull/");
+        self.0["html_url"] = html_url.into();
+        self.0["pull_request"] = serde_json::json!({});
+        self
+    }
+
+    pub(crate) fn title(mut self, title: &str) -> IssueBuilder {
+        self.0["title"] = title.into();
+        self
+    }
+
+    pub(crate) fn body(mut self, body: &str) -> IssueBuilder {
+        self.0["body"] = body.into();
+        self
+    }
+
+    pub(crate) fn labels(mut self, labels: &[&str]) -> IssueBuilder {
+        self.0["labels"] = labels
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        self
+    }
+
+    pub(crate) fn closed(mut self) -> IssueBuilder {
+        self.0["state"] = "closed".into();
+        self
+    }
+
+    pub(crate) fn merged(mut self) -> IssueBuilder {
+        self = self.closed();
+        self.0["merged"] = true.into();
+        self
+    }
+
+    /// The issue as GitHub would send it, for serving from a mock API.
+    pub(crate) fn json(self) -> serde_json::Value {
+        self.0
+    }
+
+    pub(crate) fn build(self) -> Issue {
+        serde_json::from_value(self.0).unwrap()
+    }
 }
 
 #[derive(Debug, serde::Deserialize, Eq, PartialEq)]
@@ -332,6 +474,27 @@ pub enum IssueState {
     Closed,
 }
 
+/// Whether an issue or pull request is open, closed, or (for pull requests)
+/// merged.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MergeState {
+    Open,
+    Closed,
+    Merged,
+}
+
+impl MergeState {
+    fn of(issue: &Issue) -> MergeState {
+        if issue.merged {
+            MergeState::Merged
+        } else if issue.state == IssueState::Closed {
+            MergeState::Closed
+        } else {
+            MergeState::Open
+        }
+    }
+}
+
 /// Contains only the parts of `Issue` that are needed for turning the issue title into a Zulip
 /// topic.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -358,6 +521,8 @@ impl ZulipGitHubReference {
 
 #[derive(Debug, serde::Deserialize)]
 pub struct Comment {
+    #[serde(default)]
+    pub id: u64,
     #[serde(deserialize_with = "opt_string")]
     pub body: String,
     pub html_url: String,
@@ -380,6 +545,16 @@ pub enum PullRequestReviewState {
     Pending,
 }
 
+/// A review as returned by the pull request reviews API.
+///
+/// Unlike the webhook payload, the API reports the state in upper case
+/// (e.g. `APPROVED`).
+#[derive(Debug, serde::Deserialize)]
+pub struct PullRequestReview {
+    pub user: User,
+    pub state: String,
+}
+
 fn opt_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: serde::de::Deserializer<'de>,
@@ -427,6 +602,12 @@ impl fmt::Display for IssueRepository {
     }
 }
 
+/// Whether `err` is a 404 response from the API.
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .map_or(false, |e| e.status() == Some(StatusCode::NOT_FOUND))
+}
+
 impl IssueRepository {
     fn url(&self, client: &GithubClient) -> String {
         format!(
@@ -445,9 +626,7 @@ impl IssueRepository {
         match client.send_req(client.get(&url)).await {
             Ok(_) => Ok(true),
             Err(e) => {
-                if e.downcast_ref::<reqwest::Error>()
-                    .map_or(false, |e| e.status() == Some(StatusCode::NOT_FOUND))
-                {
+                if is_not_found(&e) {
                     Ok(false)
                 } else {
                     Err(e)
@@ -566,6 +745,17 @@ impl Issue {
     }
 
     pub async fn post_comment(&self, client: &GithubClient, body: &str) -> anyhow::Result<()> {
+        self.create_comment(client, body).await?;
+        Ok(())
+    }
+
+    /// Like [`Issue::post_comment`], but returns the created comment so that
+    /// it can be edited or deleted later.
+    pub async fn create_comment(
+        &self,
+        client: &GithubClient,
+        body: &str,
+    ) -> anyhow::Result<Comment> {
         #[derive(serde::Serialize)]
         struct PostComment<'a> {
             body: &'a str,
@@ -576,9 +766,55 @@ impl Issue {
             .expect("expected api host");
         let comments_url = format!("{}{comments_path}", client.api_url);
         client
-            .send_req(client.post(&comments_url).json(&PostComment { body }))
+            .json(client.post(&comments_url).json(&PostComment { body }))
             .await
-            .context("failed to post comment")?;
+            .context("failed to post comment")
+    }
+
+    /// Returns the reviews submitted on this pull request, oldest first.
+    pub async fn reviews(&self, client: &GithubClient) -> anyhow::Result<Vec<PullRequestReview>> {
+        let url = format!(
+            "{}/pulls/{}/reviews?per_page=100",
+            self.repository().url(client),
+            self.number
+        );
+        client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("failed to get reviews of {}", self.global_id()))
+    }
+
+    /// Requests a review of this pull request from `reviewer`.
+    pub async fn request_review(
+        &self,
+        client: &GithubClient,
+        reviewer: &str,
+    ) -> anyhow::Result<()> {
+        log::info!("request_review from {} for {}", reviewer, self.global_id());
+        let url = format!(
+            "{}/pulls/{}/requested_reviewers",
+            self.repository().url(client),
+            self.number
+        );
+        #[derive(serde::Serialize)]
+        struct ReviewersReq<'a> {
+            reviewers: &'a [&'a str],
+        }
+        client
+            .send_req(client.post(&url).json(&ReviewersReq {
+                reviewers: &[reviewer],
+            }))
+            .await
+            .with_context(|| format!("failed to request review from {reviewer}"))?;
+        Ok(())
+    }
+
+    pub async fn delete_comment(&self, client: &GithubClient, id: u64) -> anyhow::Result<()> {
+        let comment_url = format!("{}/issues/comments/{}", self.repository().url(client), id);
+        client
+            .send_req(client.delete(&comment_url))
+            .await
+            .context("failed to delete comment")?;
         Ok(())
     }
 
@@ -601,14 +837,35 @@ impl Issue {
             return Ok(());
         }
 
-        client
-            .send_req(client.delete(&url))
-            .await
-            .context("failed to delete label")?;
+        match client.send_req(client.delete(&url)).await {
+            Ok(_) => {}
+            // Someone else removed it after this event was sent.
+            Err(e) if is_not_found(&e) => {
+                log::info!(
+                    "remove_label from {}: {:?} was already removed",
+                    self.global_id(),
+                    label
+                );
+            }
+            Err(e) => return Err(e.context("failed to delete label")),
+        }
 
         Ok(())
     }
 
+    /// Removes each of `labels` that is on the issue, see
+    /// [`Issue::remove_label`].
+    pub async fn remove_labels(
+        &self,
+        client: &GithubClient,
+        labels: &[&str],
+    ) -> anyhow::Result<()> {
+        for label in labels {
+            self.remove_label(client, label).await?;
+        }
+        Ok(())
+    }
+
     pub async fn add_labels(
         &self,
         client: &GithubClient,
@@ -942,10 +1199,12 @@ pub struct PullRequestFile {
     pub blob_url: String,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct Milestone {
     number: u64,
-    title: String,
+    pub title: String,
+    #[serde(default)]
+    pub due_on: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -1114,6 +1373,8 @@ pub struct Repository {
     pub default_branch: String,
     #[serde(default)]
     pub fork: bool,
+    #[serde(default)]
+    pub private: bool,
     pub parent: Option<Box<Repository>>,
 }
 
@@ -1417,6 +1678,70 @@ impl Repository {
             })
     }
 
+    /// Deletes a git reference (e.g. `heads/my-branch`).
+    pub async fn delete_reference(
+        &self,
+        client: &GithubClient,
+        refname: &str,
+    ) -> anyhow::Result<()> {
+        let url = format!("{}/git/refs/{}", self.url(client), refname);
+        client
+            .send_req(client.delete(&url))
+            .await
+            .with_context(|| format!("{} failed to delete reference {refname}", self.full_name))?;
+        Ok(())
+    }
+
+    /// Creates a new git reference (e.g. `refs/heads/my-branch`) pointing
+    /// at `sha`.
+    pub async fn create_reference(
+        &self,
+        client: &GithubClient,
+        refname: &str,
+        sha: &str,
+    ) -> anyhow::Result<GitReference> {
+        let url = format!("{}/git/refs", self.url(client));
+        client
+            .json(client.post(&url).json(&serde_json::json!({
+                "ref": refname,
+                "sha": sha,
+            })))
+            .await
+            .with_context(|| {
+                format!(
+                    "{} failed to create reference {refname} at {sha}",
+                    self.full_name
+                )
+            })
+    }
+
+    /// Merges `head` (a branch or SHA) into the branch `base`, returning the
+    /// SHA of the merge commit.
+    ///
+    /// Fails if the merge has conflicts.
+    pub async fn merge(
+        &self,
+        client: &GithubClient,
+        base: &str,
+        head: &str,
+        commit_message: &str,
+    ) -> anyhow::Result<String> {
+        #[derive(serde::Deserialize)]
+        struct MergeResponse {
+            sha: String,
+        }
+        let url = format!("{}/merges", self.url(client));
+        let merge: MergeResponse = client
+            .json(client.post(&url).json(&serde_json::json!({
+                "base": base,
+                "head": head,
+                "commit_message": commit_message,
+            })))
+            .await
+            .with_context(|| format!("{} failed to merge {head} into {base}", self.full_name))?;
+        Ok(merge.sha)
+    }
+
     /// Returns a list of recent commits on the given branch.
     ///
     /// Returns results in the OID range `oldest` (exclusive) to `newest`
@@ -1630,6 +1955,30 @@ impl Repository {
         })
     }
 
+    /// Opens a new issue.
+    pub async fn new_issue(
+        &self,
+        client: &GithubClient,
+        title: &str,
+        body: &str,
+        labels: &[String],
+    ) -> anyhow::Result<Issue> {
+        let url = format!("{}/issues", self.url(client));
+        client
+            .json(client.post(&url).json(&serde_json::json!({
+                "title": title,
+                "body": body,
+                "labels": labels,
+            })))
+            .await
+            .with_context(|| {
+                format!(
+                    "{} failed to create a new issue title={title}",
+                    self.full_name
+                )
+            })
+    }
+
     /// Creates a new PR.
     pub async fn new_pr(
         &self,
@@ -1658,6 +2007,24 @@ impl Repository {
         Ok(issue)
     }
 
+    /// Returns the pull requests that introduced the given commit, or that
+    /// contain it if it hasn't been merged yet.
+    pub async fn pulls_for_commit(
+        &self,
+        client: &GithubClient,
+        sha: &str,
+    ) -> anyhow::Result<Vec<Issue>> {
+        let url = format!("{}/commits/{sha}/pulls", self.url(client));
+        let mut pulls: Vec<Issue> = client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get pulls for commit {sha}", self.full_name))?;
+        for pr in &mut pulls {
+            pr.pull_request = Some(PullRequestDetails::new());
+        }
+        Ok(pulls)
+    }
+
     /// Synchronize a branch (in a forked repository) by pulling in its upstream contents.
     ///
     /// **Warning**: This will to a force update if there are conflicts.
@@ -1739,6 +2106,28 @@ impl Repository {
             .await
     }
 
+    /// Returns all open milestones, sorted by due date.
+    pub async fn open_milestones(&self, client: &GithubClient) -> anyhow::Result<Vec<Milestone>> {
+        let mut milestones = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "{}/milestones?state=open&sort=due_on&direction=asc&per_page=100&page={page}",
+                self.url(client)
+            );
+            let batch: Vec<Milestone> = client
+                .json(client.get(&url))
+                .await
+                .with_context(|| format!("failed to list milestones of {}", self.full_name))?;
+            let done = batch.len() < 100;
+            milestones.extend(batch);
+            if done {
+                return Ok(milestones);
+            }
+            page += 1;
+        }
+    }
+
     /// Set the milestone of an issue or PR.
     pub async fn set_milestone(
         &self,
@@ -1751,6 +2140,20 @@ impl Repository {
             .await
     }
 
+    /// Fetches an issue by number. Unlike [`Repository::get_issue`] this also
+    /// works for plain issues, but PR-specific fields are not filled in.
+    pub async fn get_issue_by_number(
+        &self,
+        client: &GithubClient,
+        issue_num: u64,
+    ) -> anyhow::Result<Issue> {
+        let url = format!("{}/issues/{issue_num}", self.url(client));
+        client
+            .json(client.get(&url))
+            .await
+            .with_context(|| format!("{} failed to get issue {issue_num}", self.full_name))
+    }
+
     pub async fn get_issue(&self, client: &GithubClient, issue_num: u64) -> anyhow::Result<Issue> {
         let url = format!("{}/pulls/{issue_num}", self.url(client));
         client
@@ -1758,6 +2161,22 @@ impl Repository {
             .await
             .with_context(|| format!("{} failed to get issue {issue_num}", self.full_name))
     }
+
+    /// Returns whether the given issue or pull request is open, closed, or
+    /// merged.
+    pub async fn merge_state(
+        &self,
+        client: &GithubClient,
+        issue_num: u64,
+    ) -> anyhow::Result<MergeState> {
+        let issue = self.get_issue_by_number(client, issue_num).await?;
+        // The issues endpoint doesn't say whether a pull request was merged.
+        if issue.is_pr() && issue.state == IssueState::Closed {
+            let pr = self.get_issue(client, issue_num).await?;
+            return Ok(MergeState::of(&pr));
+        }
+        Ok(MergeState::of(&issue))
+    }
 }
 
 pub struct Query<'a> {
@@ -2001,10 +2420,77 @@ pub struct CreateEvent {
 pub struct PushEvent {
     #[serde(rename = "ref")]
     pub git_ref: String,
+    /// The commits that were pushed, oldest first.
+    #[serde(default)]
+    pub commits: Vec<PushCommit>,
     repository: Repository,
     sender: User,
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub struct PushCommit {
+    pub id: String,
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckRunAction {
+    Created,
+    Completed,
+    Rerequested,
+    RequestedAction,
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CheckRunEvent {
+    pub action: CheckRunAction,
+    pub check_run: CheckRun,
+    pub repository: Repository,
+    pub sender: User,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CheckRun {
+    pub id: u64,
+    pub name: String,
+    pub head_sha: String,
+    /// The final state of a completed check, e.g. `success` or `failure`.
+    pub conclusion: Option<String>,
+    pub html_url: String,
+    pub output: CheckRunOutput,
+    /// The pull requests whose head matches this check run.
+    #[serde(default)]
+    pub pull_requests: Vec<CheckRunPullRequest>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CheckRunOutput {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CheckRunPullRequest {
+    pub number: u64,
+}
+
+/// A user was added to or removed from a team.
+///
+/// This is not dispatched to handlers; it only invalidates cached team
+/// membership.
+#[derive(Debug, serde::Deserialize)]
+pub struct MembershipEvent {
+    pub action: String,
+    pub member: User,
+}
+
 /// An event triggered by a webhook.
 #[derive(Debug)]
 pub enum Event {
@@ -2024,6 +2510,8 @@ pub enum Event {
     Issue(IssuesEvent),
     /// One or more commits are pushed to a repository branch or tag.
     Push(PushEvent),
+    /// A check run was created, completed or re-requested.
+    CheckRun(CheckRunEvent),
 }
 
 impl Event {
@@ -2033,6 +2521,7 @@ impl Event {
             Event::IssueComment(event) => &event.repository,
             Event::Issue(event) => &event.repository,
             Event::Push(event) => &event.repository,
+            Event::CheckRun(event) => &event.repository,
         }
     }
 
@@ -2041,7 +2530,7 @@ impl Event {
             Event::Create(_) => None,
             Event::IssueComment(event) => Some(&event.issue),
             Event::Issue(event) => Some(&event.issue),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::CheckRun(_) => None,
         }
     }
 
@@ -2051,7 +2540,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(&e.issue.body),
             Event::IssueComment(e) => Some(&e.comment.body),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::CheckRun(_) => None,
         }
     }
 
@@ -2061,7 +2550,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(&e.changes.as_ref()?.body.as_ref()?.from),
             Event::IssueComment(e) => Some(&e.changes.as_ref()?.body.as_ref()?.from),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::CheckRun(_) => None,
         }
     }
 
@@ -2071,6 +2560,7 @@ impl Event {
             Event::Issue(e) => Some(&e.issue.html_url),
             Event::IssueComment(e) => Some(&e.comment.html_url),
             Event::Push(_) => None,
+            Event::CheckRun(e) => Some(&e.check_run.html_url),
         }
     }
 
@@ -2080,6 +2570,7 @@ impl Event {
             Event::Issue(e) => &e.issue.user,
             Event::IssueComment(e) => &e.comment.user,
             Event::Push(e) => &e.sender,
+            Event::CheckRun(e) => &e.sender,
         }
     }
 
@@ -2088,7 +2579,7 @@ impl Event {
             Event::Create(_) => None,
             Event::Issue(e) => Some(e.issue.created_at.into()),
             Event::IssueComment(e) => Some(e.comment.updated_at.into()),
-            Event::Push(_) => None,
+            Event::Push(_) | Event::CheckRun(_) => None,
         }
     }
 }
@@ -2099,13 +2590,17 @@ trait RequestSend: Sized {
 
 impl RequestSend for RequestBuilder {
     fn configure(self, g: &GithubClient) -> RequestBuilder {
-        let mut auth = HeaderValue::from_maybe_shared(format!("token {}", g.token)).unwrap();
-        auth.set_sensitive(true);
         self.header(USER_AGENT, "rust-lang-triagebot")
-            .header(AUTHORIZATION, &auth)
+            .header(AUTHORIZATION, auth_header(&g.token))
     }
 }
 
+fn auth_header(token: &str) -> HeaderValue {
+    let mut auth = HeaderValue::from_maybe_shared(format!("token {}", token)).unwrap();
+    auth.set_sensitive(true);
+    auth
+}
+
 /// Finds the token in the user's environment, panicking if no suitable token
 /// can be found.
 pub fn default_token_from_env() -> String {
@@ -2150,10 +2645,144 @@ pub struct GithubClient {
     raw_url: String,
     /// If `true`, requests will sleep if it hits GitHub's rate limit.
     retry_rate_limit: bool,
+    /// When GitHub last answered (or failed to answer) an API request.
+    api_health: Arc<Mutex<ApiHealth>>,
+    /// All tokens requests may be authenticated with, starting with `token`.
+    /// Shared between clones so that they all see the same rate limits.
+    tokens: Arc<Mutex<Vec<TokenState>>>,
+}
+
+/// Tracks the outcome of API requests so that readiness checks can tell
+/// whether GitHub is reachable.
+///
+/// Client errors like 404 are normal answers and count as successes; only
+/// transport errors, server errors and rejected credentials count as
+/// failures.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ApiHealth {
+    pub last_success: Option<Instant>,
+    pub last_failure: Option<Instant>,
+    /// When [`GithubClient::check_api_health`] last probed the API.
+    pub last_probe: Option<Instant>,
+}
+
+impl ApiHealth {
+    fn record(&mut self, failed: bool, at: Instant) {
+        if failed {
+            self.last_failure = Some(at);
+        } else {
+            self.last_success = Some(at);
+        }
+    }
+
+    /// Whether the latest request succeeded, or one did within `window`.
+    ///
+    /// A client that hasn't made any requests yet is considered healthy.
+    pub fn is_healthy(&self, now: Instant, window: Duration) -> bool {
+        match (self.last_success, self.last_failure) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(success), Some(failure)) => {
+                success >= failure || now.duration_since(success) < window
+            }
+        }
+    }
+}
+
+/// A token and the rate limit GitHub last reported for it.
+#[derive(Debug)]
+struct TokenState {
+    token: String,
+    rate_limit: Option<RateLimitSnapshot>,
+}
+
+/// Picks the token with the most remaining requests.
+///
+/// Tokens that haven't been used yet, or whose limit has since reset, are
+/// assumed to have their full budget. If every token is exhausted, returns
+/// the one that resets first along with how long to wait for it.
+fn pick_token(tokens: &[TokenState], now: u64) -> (usize, Option<Duration>) {
+    let remaining = |state: &TokenState| match state.rate_limit {
+        Some(limit) if limit.reset > now => limit.remaining,
+        _ => u64::MAX,
+    };
+    let (best, best_remaining) = tokens
+        .iter()
+        .enumerate()
+        .map(|(i, state)| (i, remaining(state)))
+        .max_by_key(|&(i, remaining)| (remaining, std::cmp::Reverse(i)))
+        .expect("at least one token");
+    if best_remaining > 0 {
+        return (best, None);
+    }
+    let (soonest, reset) = tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, state)| Some((i, state.rate_limit?.reset)))
+        .min_by_key(|&(_, reset)| reset)
+        .expect("exhausted tokens have a rate limit");
+    (soonest, Some(Duration::from_secs(reset - now)))
+}
+
+/// Additional tokens from `GITHUB_TOKEN_1`, `GITHUB_TOKEN_2`, and so on,
+/// stopping at the first one that isn't set.
+fn fallback_tokens_from_env() -> Vec<String> {
+    (1..)
+        .map_while(|i| std::env::var(format!("GITHUB_TOKEN_{i}")).ok())
+        .collect()
+}
+
+/// Below this many remaining requests, writes are spaced out over the time
+/// left until the rate limit resets.
+const LOW_RATE_LIMIT_REMAINING: u64 = 100;
+
+/// The primary rate limit state as reported by GitHub's response headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RateLimitSnapshot {
+    remaining: u64,
+    /// Unix timestamp (in seconds) at which the limit resets.
+    reset: u64,
+}
+
+impl RateLimitSnapshot {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> Option<Self> {
+        let parse = |name: &str| headers.get(name)?.to_str().ok()?.parse::<u64>().ok();
+        Some(RateLimitSnapshot {
+            remaining: parse("X-RateLimit-Remaining")?,
+            reset: parse("X-RateLimit-Reset")?,
+        })
+    }
+
+    /// How long to wait before the next write, given the current time as a
+    /// Unix timestamp.
+    ///
+    /// When the remaining budget is low, the remaining requests are spread
+    /// evenly over the time until reset instead of being spent in a burst.
+    fn write_delay(&self, now: u64) -> Option<Duration> {
+        if self.remaining >= LOW_RATE_LIMIT_REMAINING || self.reset <= now {
+            return None;
+        }
+        let until_reset = Duration::from_secs(self.reset - now);
+        Some(until_reset / (self.remaining as u32 + 1))
+    }
+}
+
+fn is_write(method: &reqwest::Method) -> bool {
+    matches!(
+        *method,
+        reqwest::Method::POST
+            | reqwest::Method::PATCH
+            | reqwest::Method::PUT
+            | reqwest::Method::DELETE
+    )
 }
 
 impl GithubClient {
     pub fn new(token: String, api_url: String, graphql_url: String, raw_url: String) -> Self {
+        let tokens = vec![TokenState {
+            token: token.clone(),
+            rate_limit: None,
+        }];
         GithubClient {
             client: Client::new(),
             token,
@@ -2161,11 +2790,13 @@ impl GithubClient {
             graphql_url,
             raw_url,
             retry_rate_limit: false,
+            api_health: Default::default(),
+            tokens: Arc::new(Mutex::new(tokens)),
         }
     }
 
     pub fn new_from_env() -> Self {
-        Self::new(
+        let client = Self::new(
             default_token_from_env(),
             std::env::var("GITHUB_API_URL")
                 .unwrap_or_else(|_| "https://api.github.com".to_string()),
@@ -2173,7 +2804,20 @@ impl GithubClient {
                 .unwrap_or_else(|_| "https://api.github.com/graphql".to_string()),
             std::env::var("GITHUB_RAW_URL")
                 .unwrap_or_else(|_| "https://raw.githubusercontent.com".to_string()),
-        )
+        );
+        client
+            .tokens
+            .lock()
+            .unwrap()
+            .extend(
+                fallback_tokens_from_env()
+                    .into_iter()
+                    .map(|token| TokenState {
+                        token,
+                        rate_limit: None,
+                    }),
+            );
+        client
     }
 
     /// Sets whether or not this client will retry when it hits GitHub's rate limit.
@@ -2184,6 +2828,39 @@ impl GithubClient {
         self.retry_rate_limit = retry;
     }
 
+    pub fn api_health(&self) -> ApiHealth {
+        *self.api_health.lock().unwrap()
+    }
+
+    /// Whether GitHub is reachable, see [`ApiHealth::is_healthy`].
+    ///
+    /// If the requests made so far say it isn't, GitHub is probed with
+    /// `GET /rate_limit` (which doesn't count against the rate limit), so
+    /// that an outage is noticed to be over even if no other requests are
+    /// being made. Probes are made at most every 30 seconds.
+    pub async fn check_api_health(&self, window: Duration) -> bool {
+        const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+        let now = Instant::now();
+        {
+            let mut health = self.api_health.lock().unwrap();
+            if health.is_healthy(now, window) {
+                return true;
+            }
+            if health
+                .last_probe
+                .map_or(false, |probe| now.duration_since(probe) < PROBE_INTERVAL)
+            {
+                return false;
+            }
+            health.last_probe = Some(now);
+        }
+        let url = format!("{}/rate_limit", self.api_url);
+        if let Err(e) = self.send_req(self.get(&url)).await {
+            log::warn!("GitHub API probe failed: {e:?}");
+        }
+        self.api_health().is_healthy(Instant::now(), window)
+    }
+
     pub fn raw(&self) -> &Client {
         &self.client
     }
@@ -2312,13 +2989,7 @@ impl GithubClient {
         vars: serde_json::Value,
     ) -> anyhow::Result<serde_json::Value> {
         let result: serde_json::Value = self.graphql_query_with_errors(query, vars).await?;
-        if let Some(errors) = result["errors"].as_array() {
-            let messages: Vec<_> = errors
-                .iter()
-                .map(|err| err["message"].as_str().unwrap_or_default())
-                .collect();
-            anyhow::bail!("error: {}", messages.join("\n"));
-        }
+        check_graphql_errors(&result)?;
         Ok(result)
     }
 
@@ -2425,6 +3096,61 @@ impl GithubClient {
         }
     }
 
+    /// Runs a query against the issue search API, returning a page of up to
+    /// 100 results.
+    ///
+    /// `terms` are search qualifiers such as `is:pr` or `repo:rust-lang/rust`.
+    pub async fn search_issues(
+        &self,
+        terms: &[&str],
+        page: u32,
+    ) -> anyhow::Result<IssueSearchResult> {
+        let url = format!(
+            "{}/search/issues?q={}&per_page=100&page={page}",
+            self.api_url,
+            terms.join("+")
+        );
+        self.json(self.get(&url))
+            .await
+            .with_context(|| format!("failed to search issues with {url}"))
+    }
+
+    /// Returns the number of issues matching each of the search `queries`
+    /// (e.g. `repo:rust-lang/rust is:pr author:someone`).
+    ///
+    /// This uses GraphQL, with many searches per request, because the REST
+    /// search API only allows 30 requests per minute.
+    pub async fn search_issue_counts(&self, queries: &[String]) -> anyhow::Result<Vec<u64>> {
+        const BATCH_SIZE: usize = 50;
+        let mut counts = Vec::with_capacity(queries.len());
+        for batch in queries.chunks(BATCH_SIZE) {
+            let params: Vec<_> = (0..batch.len())
+                .map(|i| format!("$q{i}: String!"))
+                .collect();
+            let searches: String = (0..batch.len())
+                .map(|i| format!("s{i}: search(query: $q{i}, type: ISSUE) {{ issueCount }}\n"))
+                .collect();
+            let vars: serde_json::Map<_, _> = batch
+                .iter()
+                .enumerate()
+                .map(|(i, query)| (format!("q{i}"), query.as_str().into()))
+                .collect();
+            let result = self
+                .graphql_query(
+                    &format!("query({}) {{\n{searches}}}", params.join(", ")),
+                    vars.into(),
+                )
+                .await?;
+            for (i, query) in batch.iter().enumerate() {
+                let count = result["data"][format!("s{i}")]["issueCount"]
+                    .as_u64()
+                    .with_context(|| format!("no issue count for {query:?}: {result}"))?;
+                counts.push(count);
+            }
+        }
+        Ok(counts)
+    }
+
     /// Returns information about a repository.
     ///
     /// The `full_name` should be something like `rust-lang/rust`.
@@ -2528,6 +3254,21 @@ impl GithubClient {
     }
 }
 
+/// Fails with the messages of a GraphQL response's `errors`, if it has any.
+///
+/// GitHub returns these with a 200 status, and may include partial `data`
+/// alongside them.
+fn check_graphql_errors(result: &serde_json::Value) -> anyhow::Result<()> {
+    let Some(errors) = result["errors"].as_array().filter(|e| !e.is_empty()) else {
+        return Ok(());
+    };
+    let messages: Vec<_> = errors
+        .iter()
+        .map(|err| err["message"].as_str().unwrap_or_default())
+        .collect();
+    anyhow::bail!("error: {}", messages.join("\n"));
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct GithubCommit {
     pub sha: String,
@@ -2548,6 +3289,8 @@ pub struct GitCommit {
     pub author: GitUser,
     pub message: String,
     pub tree: GitCommitTree,
+    #[serde(default)]
+    pub parents: Vec<Parent>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -2984,6 +3727,278 @@ impl Submodule {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn merge_state_checks_pulls_endpoint_for_closed_prs() {
+        let server = wiremock::MockServer::start().await;
+        let serve = |path: &str, issue: IssueBuilder, times: u64| {
+            wiremock::Mock::given(wiremock::matchers::method("GET"))
+                .and(wiremock::matchers::path(path.to_string()))
+                .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(issue.json()))
+                .expect(times)
+        };
+        let issue = |number| IssueBuilder::new("rust-lang/rust", number);
+        // The issues endpoint doesn't include `merged`.
+        serve("/repos/rust-lang/rust/issues/1", issue(1).pr().closed(), 1)
+            .mount(&server)
+            .await;
+        serve("/repos/rust-lang/rust/pulls/1", issue(1).pr().merged(), 1)
+            .mount(&server)
+            .await;
+        serve("/repos/rust-lang/rust/issues/2", issue(2).pr().closed(), 1)
+            .mount(&server)
+            .await;
+        serve("/repos/rust-lang/rust/pulls/2", issue(2).pr().closed(), 1)
+            .mount(&server)
+            .await;
+        // Open pull requests and issues need only one request.
+        serve("/repos/rust-lang/rust/issues/3", issue(3).pr(), 1)
+            .mount(&server)
+            .await;
+        serve("/repos/rust-lang/rust/issues/4", issue(4).closed(), 1)
+            .mount(&server)
+            .await;
+        let client = GithubClient::new(
+            "token".to_string(),
+            server.uri(),
+            format!("{}/graphql", server.uri()),
+            server.uri(),
+        );
+        let repo = Repository {
+            full_name: "rust-lang/rust".to_string(),
+            default_branch: "master".to_string(),
+            fork: false,
+            private: false,
+            parent: None,
+        };
+        assert_eq!(
+            repo.merge_state(&client, 1).await.unwrap(),
+            MergeState::Merged
+        );
+        assert_eq!(
+            repo.merge_state(&client, 2).await.unwrap(),
+            MergeState::Closed
+        );
+        assert_eq!(
+            repo.merge_state(&client, 3).await.unwrap(),
+            MergeState::Open
+        );
+        assert_eq!(
+            repo.merge_state(&client, 4).await.unwrap(),
+            MergeState::Closed
+        );
+    }
+
+    #[tokio::test]
+    async fn removing_absent_labels_succeeds() {
+        let issue = IssueBuilder::new("rust-lang/rust", 1)
+            .labels(&["C-bug"])
+            .build();
+        let server = wiremock::MockServer::start().await;
+        let client = GithubClient::new(
+            "token".to_string(),
+            server.uri(),
+            format!("{}/graphql", server.uri()),
+            server.uri(),
+        );
+        // Labels the issue doesn't have aren't requested at all.
+        issue
+            .remove_labels(&client, &["I-nominated", "P-high"])
+            .await
+            .unwrap();
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        // Someone else removed the label after the event was sent.
+        wiremock::Mock::given(wiremock::matchers::method("DELETE"))
+            .and(wiremock::matchers::path(
+                "/repos/rust-lang/rust/issues/1/labels/C-bug",
+            ))
+            .respond_with(wiremock::ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        issue.remove_labels(&client, &["C-bug"]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn issue_counts_are_searched_in_batches() {
+        let server = wiremock::MockServer::start().await;
+        let data: serde_json::Map<_, _> = (0..50)
+            .map(|i| (format!("s{i}"), serde_json::json!({ "issueCount": 2 })))
+            .collect();
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/graphql"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "data": data })),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = GithubClient::new(
+            "token".to_string(),
+            server.uri(),
+            format!("{}/graphql", server.uri()),
+            server.uri(),
+        );
+        let queries: Vec<_> = (0..60).map(|i| format!("author:user{i}")).collect();
+        assert_eq!(client.search_issue_counts(&queries).await.unwrap(), [2; 60]);
+    }
+
+    fn status_error(status: u16) -> anyhow::Error {
+        let resp = hyper::Response::builder().status(status).body("").unwrap();
+        let err = Response::from(resp).error_for_status().unwrap_err();
+        // `send_req` adds the response body as context.
+        anyhow::Error::new(err).context("response: ")
+    }
+
+    #[test]
+    fn only_404_responses_are_not_found() {
+        assert!(is_not_found(&status_error(404)));
+        assert!(!is_not_found(&status_error(403)));
+        assert!(!is_not_found(&status_error(500)));
+        assert!(!is_not_found(&anyhow!("connection refused")));
+    }
+
+    #[test]
+    fn graphql_errors_are_reported() {
+        let ok = serde_json::json!({
+            "data": { "repository": { "id": "R_1" } },
+        });
+        assert!(check_graphql_errors(&ok).is_ok());
+
+        let partial = serde_json::json!({
+            "data": { "repository": null },
+            "errors": [
+                { "type": "NOT_FOUND", "message": "Could not resolve to a Repository" },
+                { "message": "Something else" },
+            ],
+        });
+        assert_eq!(
+            check_graphql_errors(&partial).unwrap_err().to_string(),
+            "error: Could not resolve to a Repository\nSomething else"
+        );
+    }
+
+    #[tokio::test]
+    async fn graphql_errors_fail_the_query() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .and(wiremock::matchers::path("/graphql"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "data": { "s0": null },
+                    "errors": [{ "type": "RATE_LIMITED", "message": "API rate limit exceeded" }],
+                })),
+            )
+            .mount(&server)
+            .await;
+        let client = GithubClient::new(
+            "token".to_string(),
+            server.uri(),
+            format!("{}/graphql", server.uri()),
+            server.uri(),
+        );
+        let err = client
+            .search_issue_counts(&["author:octocat".to_string()])
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "error: API rate limit exceeded");
+
+        // The errors are left to the caller here.
+        let result = client
+            .graphql_query_with_errors("query { viewer { login } }", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(result["errors"][0]["type"], "RATE_LIMITED");
+    }
+
+    #[test]
+    fn low_remaining_rate_limit_delays_writes() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("9"));
+        headers.insert("X-RateLimit-Reset", HeaderValue::from_static("1100"));
+        let snapshot = RateLimitSnapshot::from_headers(&headers).unwrap();
+        assert_eq!(snapshot.write_delay(1000), Some(Duration::from_secs(10)));
+        // Once the limit has reset there is nothing to wait for.
+        assert_eq!(snapshot.write_delay(1100), None);
+
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("4000"));
+        let snapshot = RateLimitSnapshot::from_headers(&headers).unwrap();
+        assert_eq!(snapshot.write_delay(1000), None);
+    }
+
+    #[test]
+    fn api_health_tolerates_old_failures() {
+        let start = Instant::now();
+        let window = Duration::from_secs(300);
+        let mut health = ApiHealth::default();
+        assert!(health.is_healthy(start, window));
+
+        health.record(true, start);
+        assert!(!health.is_healthy(start, window));
+
+        health.record(false, start + Duration::from_secs(10));
+        health.record(true, start + Duration::from_secs(20));
+        assert!(health.is_healthy(start + Duration::from_secs(60), window));
+        assert!(!health.is_healthy(start + Duration::from_secs(400), window));
+
+        health.record(false, start + Duration::from_secs(500));
+        assert!(health.is_healthy(start + Duration::from_secs(10_000), window));
+    }
+
+    #[tokio::test]
+    async fn unhealthy_api_is_probed() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/rate_limit"))
+            .respond_with(wiremock::ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = GithubClient::new(
+            "token".to_string(),
+            server.uri(),
+            format!("{}/graphql", server.uri()),
+            server.uri(),
+        );
+        // Only the latest request counts.
+        let window = Duration::ZERO;
+        client
+            .api_health
+            .lock()
+            .unwrap()
+            .record(true, Instant::now());
+        assert!(client.check_api_health(window).await);
+
+        // Probes aren't repeated right away.
+        client
+            .api_health
+            .lock()
+            .unwrap()
+            .record(true, Instant::now() + Duration::from_secs(1));
+        assert!(!client.check_api_health(window).await);
+    }
+
+    #[test]
+    fn token_with_most_remaining_requests_is_picked() {
+        let state = |remaining, reset| TokenState {
+            token: String::new(),
+            rate_limit: Some(RateLimitSnapshot { remaining, reset }),
+        };
+        let tokens = [state(10, 2000), state(500, 2000), state(20, 2000)];
+        assert_eq!(pick_token(&tokens, 1000), (1, None));
+
+        // A token whose limit has already reset is as good as new.
+        let tokens = [state(10, 2000), state(0, 900)];
+        assert_eq!(pick_token(&tokens, 1000), (1, None));
+
+        let tokens = [state(0, 1600), state(0, 1300)];
+        assert_eq!(
+            pick_token(&tokens, 1000),
+            (1, Some(Duration::from_secs(300)))
+        );
+    }
+
     #[test]
     fn display_labels() {
         let x = UnknownLabels {
@@ -1,9 +1,14 @@
 use crate::config::{self, Config, ConfigurationError};
-use crate::github::{Event, GithubClient, IssueCommentAction, IssuesAction, IssuesEvent};
+use crate::db::rate_limit::RateLimitConfig;
+use crate::github::{Event, GithubClient, IssueCommentAction, IssuesAction, IssuesEvent, User};
+use crate::shutdown::ShutdownCoordinator;
+use crate::team_data::TeamMembershipCache;
 use octocrab::Octocrab;
+use once_cell::sync::Lazy;
 use parser::command::{assign::AssignCommand, Command, Input};
+use std::collections::HashSet;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tracing as log;
 
 #[derive(Debug)]
@@ -24,29 +29,55 @@ impl fmt::Display for HandlerError {
 }
 
 mod assign;
+mod auto_review_request;
 mod autolabel;
+mod changelog;
+mod cherry_pick;
+mod ci_status;
 mod close;
+mod conflict_notify;
+pub mod contributor_digest;
+mod crater;
+pub mod dep_audit;
 pub mod docs_update;
+pub mod edition;
+pub mod feature_tracking;
 mod github_releases;
 mod glacier;
+pub mod job_watchdog;
+mod keyword_label;
 mod major_change;
+pub mod meeting_agenda;
 mod mentions;
+mod milestone;
 mod milestone_prs;
+mod msrv;
+pub mod needs_info;
 mod no_merges;
 mod nominate;
 mod note;
 mod notification;
 mod notify_zulip;
+pub mod on_call;
+mod perf;
 mod ping;
 pub mod pr_tracking;
 mod prioritize;
+mod priority_score;
 pub mod pull_requests_assignment_update;
+mod push_trigger;
+mod regression_link;
 mod relabel;
+mod required_review;
 mod review_requested;
 mod review_submitted;
 mod rfc_helper;
+mod rollup;
 pub mod rustc_commits;
+mod semver;
 mod shortcut;
+pub mod sla;
+pub mod toolchain_update;
 mod transfer;
 pub mod types_planning_updates;
 mod validate_config;
@@ -98,6 +129,14 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         );
     }
 
+    if let Err(e) = crater::handle(ctx, event).await {
+        log::error!(
+            "failed to process event {:?} with crater handler: {:?}",
+            event,
+            e
+        );
+    }
+
     if let Some(config) = config
         .as_ref()
         .ok()
@@ -112,6 +151,234 @@ pub async fn handle(ctx: &Context, event: &Event) -> Vec<HandlerError> {
         }
     }
 
+    if let Some(digest_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.contributor_digest.as_ref())
+    {
+        if let Err(e) = contributor_digest::handle(ctx, event, digest_config).await {
+            log::error!(
+                "failed to process event {:?} with contributor_digest handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(agenda_config) = config.as_ref().ok().and_then(|c| c.meeting_agenda.as_ref()) {
+        if let Err(e) = meeting_agenda::handle(ctx, event, agenda_config).await {
+            log::error!(
+                "failed to process event {:?} with meeting_agenda handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(perf_config) = config.as_ref().ok().and_then(|c| c.perf.as_ref()) {
+        if let Err(e) = perf::handle(ctx, event, perf_config).await {
+            log::error!(
+                "failed to process event {:?} with perf handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(ci_config) = config.as_ref().ok().and_then(|c| c.ci_status.as_ref()) {
+        if let Err(e) = ci_status::handle(ctx, event, ci_config).await {
+            log::error!(
+                "failed to process event {:?} with ci_status handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(required_review_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.required_review.as_ref())
+    {
+        if let Err(e) = required_review::handle(ctx, event, required_review_config).await {
+            log::error!(
+                "failed to process event {:?} with required_review handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(auto_review_request_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.auto_review_request.as_ref())
+    {
+        if let Err(e) = auto_review_request::handle(ctx, event, auto_review_request_config).await {
+            log::error!(
+                "failed to process event {:?} with auto_review_request handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(conflict_notify_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.conflict_notify.as_ref())
+    {
+        if let Err(e) = conflict_notify::handle(ctx, event, conflict_notify_config).await {
+            log::error!(
+                "failed to process event {:?} with conflict_notify handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(regression_link_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.regression_link.as_ref())
+    {
+        if let Err(e) = regression_link::handle(ctx, event, regression_link_config).await {
+            log::error!(
+                "failed to process event {:?} with regression_link handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(priority_scoring_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.priority_scoring.as_ref())
+    {
+        if let Err(e) = priority_score::handle(ctx, event, priority_scoring_config).await {
+            log::error!(
+                "failed to process event {:?} with priority_score handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(sla_config) = config.as_ref().ok().and_then(|c| c.sla.as_ref()) {
+        if let Err(e) = sla::handle(ctx, event, sla_config).await {
+            log::error!(
+                "failed to process event {:?} with sla handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(on_call_config) = config.as_ref().ok().and_then(|c| c.on_call.as_ref()) {
+        if let Err(e) = on_call::handle(ctx, event, on_call_config).await {
+            log::error!(
+                "failed to process event {:?} with on_call handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(dep_audit_config) = config.as_ref().ok().and_then(|c| c.dep_audit.as_ref()) {
+        if let Err(e) = dep_audit::handle(ctx, event, dep_audit_config).await {
+            log::error!(
+                "failed to process event {:?} with dep_audit handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(msrv_config) = config.as_ref().ok().and_then(|c| c.msrv.as_ref()) {
+        if let Err(e) = msrv::handle(ctx, event, msrv_config).await {
+            log::error!(
+                "failed to process event {:?} with msrv handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(changelog_config) = config.as_ref().ok().and_then(|c| c.changelog.as_ref()) {
+        if let Err(e) = changelog::handle(ctx, event, changelog_config).await {
+            log::error!(
+                "failed to process event {:?} with changelog handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(semver_config) = config.as_ref().ok().and_then(|c| c.semver.as_ref()) {
+        if let Err(e) = semver::handle(ctx, event, semver_config).await {
+            log::error!(
+                "failed to process event {:?} with semver handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(toolchain_update_config) = config
+        .as_ref()
+        .ok()
+        .and_then(|c| c.toolchain_update.as_ref())
+    {
+        if let Err(e) = toolchain_update::handle(ctx, event, toolchain_update_config).await {
+            log::error!(
+                "failed to process event {:?} with toolchain_update handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(needs_info_config) = config.as_ref().ok().and_then(|c| c.needs_info.as_ref()) {
+        if let Err(e) = needs_info::handle(ctx, event, needs_info_config).await {
+            log::error!(
+                "failed to process event {:?} with needs_info handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(edition_config) = config.as_ref().ok().and_then(|c| c.edition.as_ref()) {
+        if let Err(e) = edition::handle(ctx, event, edition_config).await {
+            log::error!(
+                "failed to process event {:?} with edition handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(rollup_config) = config.as_ref().ok().and_then(|c| c.rollup.as_ref()) {
+        if let Err(e) = rollup::handle(ctx, event, rollup_config).await {
+            log::error!(
+                "failed to process event {:?} with rollup handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
+    if let Some(push_trigger_config) = config.as_ref().ok().and_then(|c| c.push_trigger.as_ref()) {
+        if let Err(e) = push_trigger::handle(ctx, event, push_trigger_config).await {
+            log::error!(
+                "failed to process event {:?} with push_trigger handler: {:?}",
+                event,
+                e
+            );
+        }
+    }
+
     if let Some(ghr_config) = config
         .as_ref()
         .ok()
@@ -165,8 +432,11 @@ macro_rules! issue_handlers {
 issue_handlers! {
     assign,
     autolabel,
+    feature_tracking,
+    keyword_label,
     major_change,
     mentions,
+    milestone,
     no_merges,
     notify_zulip,
     review_requested,
@@ -204,16 +474,16 @@ macro_rules! command_handlers {
                     log::debug!("skipping event, comment was {:?}", e.action);
                     return;
                 }
-                Event::Push(_) | Event::Create(_) => {
+                Event::Push(_) | Event::Create(_) | Event::CheckRun(_) => {
                     log::debug!("skipping unsupported event");
                     return;
                 }
             }
 
-            let input = Input::new(&body, vec![&ctx.username, "triagebot"]);
+            let input = Input::new(&body, vec![&ctx.username, "triagebot"]).with_source();
             let commands = if let Some(previous) = event.comment_from() {
                 let prev_commands = Input::new(&previous, vec![&ctx.username, "triagebot"]).collect::<Vec<_>>();
-                input.filter(|cmd| !prev_commands.contains(cmd)).collect::<Vec<_>>()
+                input.filter(|(cmd, _)| !prev_commands.contains(cmd)).collect::<Vec<_>>()
             } else {
                 input.collect()
             };
@@ -232,7 +502,7 @@ macro_rules! command_handlers {
                     // case, just ignore it.
                     if commands
                         .iter()
-                        .all(|cmd| matches!(cmd, Command::Assign(Ok(AssignCommand::ReviewName { .. }))))
+                        .all(|(cmd, _)| matches!(cmd, Command::Assign(Ok(AssignCommand::ReviewName { .. }))))
                     {
                         return;
                     }
@@ -246,22 +516,45 @@ macro_rules! command_handlers {
                 }
             };
 
-            for command in commands {
+            for (command, source) in commands {
                 match command {
                     $(
                     Command::$enum(Ok(command)) => {
-                        if let Some(config) = &config.$name {
-                            $name::handle_command(ctx, config, event, command)
-                                .await
-                                .unwrap_or_else(|err| errors.push(HandlerError::Other(err)));
-                        } else {
+                        let Some(feature_config) = &config.$name else {
                             errors.push(HandlerError::Message(format!(
                                 "The feature `{}` is not enabled in this repository.\n\
                                 To enable it add its section in the `triagebot.toml` \
                                 in the root of the repository.",
                                 stringify!($name)
                             )));
+                            continue;
+                        };
+                        let name = stringify!($name).replace('_', "-");
+                        match may_run_command(ctx, config, &name, event.user()).await {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                errors.push(HandlerError::Message(format!(
+                                    "@{} is not allowed to use the `{}` command in this repository.",
+                                    event.user().login,
+                                    name
+                                )));
+                                continue;
+                            }
+                            Err(err) => {
+                                errors.push(HandlerError::Other(err));
+                                continue;
+                            }
                         }
+                        if !within_rate_limit(ctx, event.user()).await {
+                            errors.push(HandlerError::Message(
+                                "You're sending commands too quickly; please wait before trying again.".to_string(),
+                            ));
+                            continue;
+                        }
+                        let audit_id = audit_command_start(ctx, event, source).await;
+                        let result = $name::handle_command(ctx, feature_config, event, command).await;
+                        audit_command_end(ctx, audit_id, &result).await;
+                        result.unwrap_or_else(|err| errors.push(HandlerError::Other(err)));
                     }
                     Command::$enum(Err(err)) => {
                         errors.push(HandlerError::Message(format!(
@@ -294,6 +587,12 @@ command_handlers! {
     close: Close,
     note: Note,
     transfer: Transfer,
+    perf: Perf,
+    cherry_pick: CherryPick,
+    on_call: OnCall,
+    semver: Semver,
+    crater: Crater,
+    edition: EditionCompat,
 }
 
 pub struct Context {
@@ -301,4 +600,135 @@ pub struct Context {
     pub db: crate::db::ClientPool,
     pub username: String,
     pub octocrab: Octocrab,
+    /// Open milestones per repository, used by the milestone handler.
+    pub milestone_cache: Arc<Mutex<milestone::MilestoneCache>>,
+    pub team_membership_cache: Arc<Mutex<TeamMembershipCache>>,
+    /// How many commands each user may send per minute.
+    pub rate_limit: RateLimitConfig,
+    pub shutdown: ShutdownCoordinator,
+}
+
+impl Context {
+    /// Like [`User::is_team_member`], but answers from the membership cache
+    /// when possible.
+    pub async fn is_team_member(&self, user: &User) -> anyhow::Result<bool> {
+        let cached = self.team_membership_cache.lock().unwrap().get(&user.login);
+        if let Some(is_member) = cached {
+            return Ok(is_member);
+        }
+        let is_member = user.is_team_member(&self.github).await?;
+        self.team_membership_cache
+            .lock()
+            .unwrap()
+            .insert(&user.login, is_member);
+        Ok(is_member)
+    }
+
+    /// Like [`crate::team_data::teams`], but answers from the membership
+    /// cache when possible.
+    pub async fn teams(&self) -> anyhow::Result<Arc<rust_team_data::v1::Teams>> {
+        let cached = self.team_membership_cache.lock().unwrap().get_teams();
+        if let Some(teams) = cached {
+            return Ok(teams);
+        }
+        let teams = Arc::new(crate::team_data::teams(&self.github).await?);
+        self.team_membership_cache
+            .lock()
+            .unwrap()
+            .insert_teams(teams.clone());
+        Ok(teams)
+    }
+}
+
+/// Registers `repo` as using `feature`, see [`crate::db::registered_repos`].
+///
+/// The handlers of features run by a periodic job call this for every event
+/// from a repository with the feature's section in its `triagebot.toml`, so
+/// a repository is registered the first time triagebot sees an event for it.
+/// The job then goes through the registered repositories.
+///
+/// Registrations are remembered for the lifetime of the process, so that
+/// only the first event from a repository hits the database.
+async fn register_repo(ctx: &Context, feature: &'static str, repo: &str) -> anyhow::Result<()> {
+    static REGISTERED: Lazy<Mutex<HashSet<(&'static str, String)>>> = Lazy::new(Default::default);
+    let key = (feature, repo.to_string());
+    if REGISTERED.lock().unwrap().contains(&key) {
+        return Ok(());
+    }
+    crate::db::registered_repos::register_repo(&*ctx.db.get().await, feature, repo).await?;
+    REGISTERED.lock().unwrap().insert(key);
+    Ok(())
+}
+
+/// Counts a command against the user's per-minute limit, returning whether
+/// it may run.
+///
+/// If the limit can't be checked, the command is allowed.
+async fn within_rate_limit(ctx: &Context, user: &User) -> bool {
+    let db = ctx.db.get().await;
+    crate::db::rate_limit::check_and_increment_rate_limit(
+        &*db,
+        &user.login,
+        ctx.rate_limit.max_commands_per_minute,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("failed to check command rate limit: {e:?}");
+        true
+    })
+}
+
+/// Records a command invocation in the audit log, returning its id.
+///
+/// Failing to write the audit log is logged but doesn't stop the command.
+async fn audit_command_start(ctx: &Context, event: &Event, command: &str) -> Option<i64> {
+    let db = ctx.db.get().await;
+    crate::db::audit::record_command_invocation(
+        &*db,
+        &event.repo().full_name,
+        event.issue().map(|issue| issue.number),
+        command,
+        &event.user().login,
+    )
+    .await
+    .map_err(|e| log::error!("failed to record command invocation: {e:?}"))
+    .ok()
+}
+
+async fn audit_command_end(ctx: &Context, id: Option<i64>, result: &anyhow::Result<()>) {
+    let Some(id) = id else { return };
+    let db = ctx.db.get().await;
+    if let Err(e) = crate::db::audit::record_command_result(&*db, id, result).await {
+        log::error!("failed to record command result: {e:?}");
+    }
+}
+
+/// Checks `user` against the `command-permissions` entry for `command`.
+///
+/// Commands without an entry may be used by anyone. Otherwise the user must
+/// be listed by login, be a member of one of the listed teams, or, if `*` is
+/// listed, be a member of any team.
+async fn may_run_command(
+    ctx: &Context,
+    config: &Config,
+    command: &str,
+    user: &User,
+) -> anyhow::Result<bool> {
+    let Some(allowed) = config.command_permissions.get(command) else {
+        return Ok(true);
+    };
+    if allowed.iter().any(|entry| *entry == user.login) {
+        return Ok(true);
+    }
+    if allowed.iter().any(|entry| entry == "*") && ctx.is_team_member(user).await? {
+        return Ok(true);
+    }
+    if allowed.iter().all(|entry| entry == "*") {
+        return Ok(false);
+    }
+    let teams = ctx.teams().await?;
+    Ok(allowed
+        .iter()
+        .filter_map(|entry| teams.teams.get(entry))
+        .any(|team| team.members.iter().any(|m| m.github == user.login)))
 }
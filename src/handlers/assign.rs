@@ -430,8 +430,7 @@ pub(super) async fn handle_command(
     event: &Event,
     cmd: AssignCommand,
 ) -> anyhow::Result<()> {
-    let is_team_member = if let Err(_) | Ok(false) = event.user().is_team_member(&ctx.github).await
-    {
+    let is_team_member = if let Err(_) | Ok(false) = ctx.is_team_member(event.user()).await {
         false
     } else {
         true
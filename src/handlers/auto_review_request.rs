@@ -0,0 +1,87 @@
+//! Requests reviews from a team when a label is added to a PR.
+//!
+//! For each `[[auto-review-request]]` entry, adding `label` to a pull request
+//! requests a review from every member of `team` other than the PR author, or
+//! from `max-reviewers` of them chosen at random.
+
+use crate::{
+    config::AutoReviewRequestConfig,
+    github::{self, Event, IssuesAction},
+    handlers::Context,
+};
+use rand::seq::IteratorRandom;
+use tracing as log;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &[AutoReviewRequestConfig],
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    let IssuesAction::Labeled { label } = &e.action else {
+        return Ok(());
+    };
+    if !e.issue.is_pr() {
+        return Ok(());
+    }
+
+    for request in config.iter().filter(|r| r.label == label.name) {
+        let Some(team) = github::get_team(&ctx.github, &request.team).await? else {
+            log::warn!(
+                "auto-review-request team {} for {} does not exist",
+                request.team,
+                e.issue.global_id()
+            );
+            continue;
+        };
+        let members = team.members.iter().map(|m| m.github.as_str());
+        for reviewer in pick_reviewers(members, &e.issue.user.login, request.max_reviewers) {
+            // Some users can't be requested (e.g. they aren't collaborators);
+            // that shouldn't keep the others from being requested.
+            if let Err(err) = e.issue.request_review(&ctx.github, reviewer).await {
+                log::warn!(
+                    "failed to request review from {reviewer} on {}: {err:?}",
+                    e.issue.global_id()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The members to request a review from: everyone but the author, or at most
+/// `max` of them chosen at random.
+fn pick_reviewers<'a>(
+    members: impl Iterator<Item = &'a str>,
+    author: &str,
+    max: Option<usize>,
+) -> Vec<&'a str> {
+    let candidates = members.filter(|m| !m.eq_ignore_ascii_case(author));
+    match max {
+        Some(max) => candidates.choose_multiple(&mut rand::thread_rng(), max),
+        None => candidates.collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn author_is_never_requested() {
+        let members = ["alice", "bob", "carol", "dave"];
+        assert_eq!(
+            pick_reviewers(members.into_iter(), "Bob", None),
+            ["alice", "carol", "dave"]
+        );
+        let picked = pick_reviewers(members.into_iter(), "bob", Some(2));
+        assert_eq!(picked.len(), 2);
+        assert!(!picked.contains(&"bob"));
+        assert_eq!(
+            pick_reviewers(members.into_iter(), "bob", Some(10)).len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Asks for a changelog entry on PRs that need one.
+//!
+//! When a PR with one of the `required-for-labels` is opened or pushed to
+//! without touching `changelog-file`, it is labeled `needs-changelog` and a
+//! comment asks for an update. The comment is recorded in `changelog_nags`
+//! so it is only posted once. Once a later push updates the changelog, the
+//! label is removed and the author is thanked.
+
+use crate::{
+    config::ChangelogConfig,
+    db::changelog_nags::{get_changelog_nag, record_changelog_nag, take_changelog_nag},
+    github::{Event, IssuesAction, Label},
+    handlers::Context,
+};
+use tracing as log;
+
+const NEEDS_CHANGELOG_LABEL: &str = "needs-changelog";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &ChangelogConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !e.issue.is_pr()
+        || !matches!(
+            e.action,
+            IssuesAction::Opened | IssuesAction::Reopened | IssuesAction::Synchronize
+        )
+    {
+        return Ok(());
+    }
+    let required = e
+        .issue
+        .labels()
+        .iter()
+        .any(|l| config.required_for_labels.contains(&l.name));
+    if !required {
+        return Ok(());
+    }
+
+    let updated = e
+        .issue
+        .files(&ctx.github)
+        .await?
+        .iter()
+        .any(|file| file.filename == config.changelog_file);
+    let repo = e.repository.full_name.as_str();
+    let db = ctx.db.get().await;
+
+    if updated {
+        if take_changelog_nag(&*db, repo, e.issue.number)
+            .await?
+            .is_some()
+        {
+            log::info!("{} updated the changelog", e.issue.global_id());
+            e.issue
+                .remove_label(&ctx.github, NEEDS_CHANGELOG_LABEL)
+                .await?;
+            e.issue
+                .post_comment(
+                    &ctx.github,
+                    &format!("Thank you for updating `{}`!", config.changelog_file),
+                )
+                .await?;
+        }
+    } else if get_changelog_nag(&*db, repo, e.issue.number)
+        .await?
+        .is_none()
+    {
+        log::info!("{} needs a changelog entry", e.issue.global_id());
+        e.issue
+            .add_labels(
+                &ctx.github,
+                vec![Label {
+                    name: NEEDS_CHANGELOG_LABEL.to_string(),
+                }],
+            )
+            .await?;
+        let comment = e
+            .issue
+            .create_comment(
+                &ctx.github,
+                &format!(
+                    "@{} this PR needs an entry in `{}`. Please add one describing the change.",
+                    e.issue.user.login, config.changelog_file
+                ),
+            )
+            .await?;
+        record_changelog_nag(&*db, repo, e.issue.number, comment.id).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Handles the `@rustbot cherry-pick <branch>` command, which backports a
+//! merged PR to one of the `allowed-base-branches`.
+//!
+//! The GitHub API can't cherry-pick directly, so this builds the commit in a
+//! few steps on a new `cp-{number}-{branch}` branch:
+//!
+//! 1. Create the branch at the head of `branch`.
+//! 2. Point it at a temporary commit with the branch's tree whose parent is
+//!    the merge commit's first parent.
+//! 3. Merge the merge commit into it. Since the merge base is that parent,
+//!    this applies exactly the PR's changes to the branch's tree.
+//! 4. Replace the result with a commit of the merged tree on top of the head
+//!    of `branch`.
+//!
+//! A PR from that branch into `branch` is then opened and linked from the
+//! original PR. If any step fails (usually because the changes conflict with
+//! `branch`), the branch is deleted again so that the command can be retried.
+
+use crate::{
+    config::CherryPickConfig,
+    github::{Event, Issue, Repository},
+    handlers::Context,
+};
+use anyhow::Context as _;
+use parser::command::cherry_pick::CherryPickCommand;
+use tracing as log;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &CherryPickConfig,
+    event: &Event,
+    input: CherryPickCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        issue
+            .post_comment(&ctx.github, "Only pull requests can be cherry-picked.")
+            .await?;
+        return Ok(());
+    }
+    if !ctx.is_team_member(event.user()).await.ok().unwrap_or(false) {
+        issue
+            .post_comment(
+                &ctx.github,
+                "Only team members may use the `cherry-pick` command.",
+            )
+            .await?;
+        return Ok(());
+    }
+    let branch = input.0;
+    if !config.allowed_base_branches.contains(&branch) {
+        issue
+            .post_comment(
+                &ctx.github,
+                &format!(
+                    "`{branch}` is not a branch PRs can be cherry-picked to. \
+                     The allowed branches are: {}.",
+                    config
+                        .allowed_base_branches
+                        .iter()
+                        .map(|b| format!("`{b}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let repo = event.repo();
+    // Webhook payloads for comments don't say whether the PR was merged.
+    let pr = repo.get_issue(&ctx.github, issue.number).await?;
+    let Some(merge_commit) = pr.merge_commit_sha.as_deref().filter(|_| pr.merged) else {
+        issue
+            .post_comment(
+                &ctx.github,
+                "Only merged pull requests can be cherry-picked.",
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let backport = match backport(ctx, repo, &pr, merge_commit, &branch).await {
+        Ok(backport) => backport,
+        Err(e) => {
+            log::error!(
+                "failed to cherry-pick {} to {branch}: {e:?}",
+                issue.global_id()
+            );
+            issue
+                .post_comment(
+                    &ctx.github,
+                    &format!(
+                        "Failed to cherry-pick to `{branch}`, most likely because the \
+                         changes conflict with it. The backport will have to be done by hand."
+                    ),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+    issue
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "Opened {} to backport this to `{branch}`.",
+                backport.html_url
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+async fn backport(
+    ctx: &Context,
+    repo: &Repository,
+    pr: &Issue,
+    merge_commit: &str,
+    branch: &str,
+) -> anyhow::Result<Issue> {
+    let client = &ctx.github;
+    let merge_commit = repo.git_commit(client, merge_commit).await?;
+    let pr_base = &merge_commit
+        .parents
+        .first()
+        .context("merge commit has no parents")?
+        .sha;
+    let base_head = repo
+        .get_reference(client, &format!("heads/{branch}"))
+        .await?
+        .object
+        .sha;
+    let base_tree = repo.git_commit(client, &base_head).await?.tree.sha;
+
+    let cp_branch = format!("cp-{}-{branch}", pr.number);
+    repo.create_reference(client, &format!("refs/heads/{cp_branch}"), &base_head)
+        .await?;
+    let backport = async {
+        let sibling = repo
+            .create_commit(
+                client,
+                "temporary cherry-pick commit",
+                &[pr_base.as_str()],
+                &base_tree,
+            )
+            .await?;
+        repo.update_reference(client, &format!("heads/{cp_branch}"), &sibling.sha)
+            .await?;
+        let merged = repo
+            .merge(
+                client,
+                &cp_branch,
+                &merge_commit.sha,
+                "temporary cherry-pick merge",
+            )
+            .await?;
+        let merged_tree = repo.git_commit(client, &merged).await?.tree.sha;
+        let cherry_picked = repo
+            .create_commit(
+                client,
+                &merge_commit.message,
+                &[base_head.as_str()],
+                &merged_tree,
+            )
+            .await?;
+        repo.update_reference(client, &format!("heads/{cp_branch}"), &cherry_picked.sha)
+            .await?;
+
+        repo.new_pr(
+            client,
+            &format!("[backport] {}", pr.title),
+            &cp_branch,
+            branch,
+            &format!("Backport of #{} to `{branch}`.", pr.number),
+        )
+        .await
+    }
+    .await;
+    if backport.is_err() {
+        if let Err(e) = repo
+            .delete_reference(client, &format!("heads/{cp_branch}"))
+            .await
+        {
+            log::error!("failed to delete {cp_branch} after a failed cherry-pick: {e:?}");
+        }
+    }
+    backport
+}
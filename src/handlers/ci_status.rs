@@ -0,0 +1,141 @@
+//! Labels pull requests whose CI failed with a known flaky test signature.
+//!
+//! When a check run completes with a `failure` conclusion, its output is
+//! matched against the configured patterns. On a match the PR is labelled
+//! and a comment is posted linking to the check run so it can be re-run. If
+//! a later run of the same check fails with the same pattern again, the
+//! configured team is pinged since the failure is probably not that flaky.
+
+use crate::{
+    config::FlakyCiConfig,
+    db::issue_data::IssueData,
+    github::{CheckRunAction, CheckRunEvent, Event, Label},
+    handlers::Context,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing as log;
+
+const CI_STATUS_KEY: &str = "ci_status";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CiStatusState {
+    /// Check name -> flaky patterns that already matched a failure of it.
+    flaky_matches: HashMap<String, Vec<String>>,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &FlakyCiConfig,
+) -> anyhow::Result<()> {
+    let event = match event {
+        Event::CheckRun(event) => event,
+        _ => return Ok(()),
+    };
+    if event.action != CheckRunAction::Completed
+        || event.check_run.conclusion.as_deref() != Some("failure")
+    {
+        return Ok(());
+    }
+    let pattern = match matching_pattern(&config.patterns, &check_output(event))? {
+        Some(pattern) => pattern,
+        None => return Ok(()),
+    };
+
+    for pr in &event.check_run.pull_requests {
+        let issue = event.repository.get_issue(&ctx.github, pr.number).await?;
+        let mut db = ctx.db.get().await;
+        let mut state: IssueData<'_, CiStatusState> =
+            IssueData::load(&mut db, &issue, CI_STATUS_KEY).await?;
+        let seen = state
+            .data
+            .flaky_matches
+            .entry(event.check_run.name.clone())
+            .or_default();
+        let repeated = seen.contains(&pattern);
+        if !repeated {
+            seen.push(pattern.clone());
+        }
+        state.save().await?;
+
+        let message = if repeated {
+            format!(
+                "The `{}` check failed again with the same known flaky failure \
+                 (`{}`), see [the check run]({}).\n\ncc {}",
+                event.check_run.name, pattern, event.check_run.html_url, config.escalate_to
+            )
+        } else {
+            if let Err(e) = issue
+                .add_labels(
+                    &ctx.github,
+                    vec![Label {
+                        name: config.label.clone(),
+                    }],
+                )
+                .await
+            {
+                log::warn!(
+                    "failed to add {} to {}: {e:?}",
+                    config.label,
+                    issue.html_url
+                );
+            }
+            format!(
+                "The `{}` check failed with a known flaky test signature (`{}`). \
+                 It can be re-run from [the check run page]({}).",
+                event.check_run.name, pattern, event.check_run.html_url
+            )
+        };
+        issue.post_comment(&ctx.github, &message).await?;
+    }
+    Ok(())
+}
+
+fn check_output(event: &CheckRunEvent) -> String {
+    let output = &event.check_run.output;
+    [&output.title, &output.summary, &output.text]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the first pattern that matches `output`.
+fn matching_pattern(patterns: &[String], output: &str) -> anyhow::Result<Option<String>> {
+    for pattern in patterns {
+        let re = Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("invalid flaky CI pattern `{pattern}`: {e}"))?;
+        if re.is_match(output) {
+            return Ok(Some(pattern.clone()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_pattern_wins() {
+        let patterns = vec![
+            r"spurious network error".to_string(),
+            r"test \S+ has been running for over \d+ seconds".to_string(),
+        ];
+        let output = "failures:\n\
+                      test sync::mpsc::recv_timeout has been running for over 60 seconds";
+        assert_eq!(
+            matching_pattern(&patterns, output).unwrap(),
+            Some(patterns[1].clone())
+        );
+        assert_eq!(matching_pattern(&patterns, "error[E0308]").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        assert!(matching_pattern(&["(".to_string()], "anything").is_err());
+    }
+}
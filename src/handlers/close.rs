@@ -10,11 +10,7 @@ pub(super) async fn handle_command(
     _cmd: CloseCommand,
 ) -> anyhow::Result<()> {
     let issue = event.issue().unwrap();
-    let is_team_member = event
-        .user()
-        .is_team_member(&ctx.github)
-        .await
-        .unwrap_or(false);
+    let is_team_member = ctx.is_team_member(event.user()).await.unwrap_or(false);
     if !is_team_member {
         let cmnt = ErrorComment::new(&issue, "Only team members can close issues.");
         cmnt.post(&ctx.github).await?;
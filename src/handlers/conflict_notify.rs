@@ -0,0 +1,107 @@
+//! Tells PR authors when their PR has merge conflicts.
+//!
+//! When a pull request event reports the PR as not mergeable, the configured
+//! label is added and `comment-template` is posted. Once the PR is mergeable
+//! again the label is removed, and if the conflict comment was posted a
+//! follow-up says the conflict is resolved.
+
+use crate::{
+    config::ConflictNotifyConfig,
+    db::conflict_notifications::{record_conflict_notification, take_conflict_notification},
+    github::{Event, Issue, Label},
+    handlers::Context,
+};
+use tracing as log;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &ConflictNotifyConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !e.issue.is_pr() || !e.issue.is_open() {
+        return Ok(());
+    }
+    let labeled = e.issue.labels().iter().any(|l| l.name == config.label);
+    match e.issue.mergeable {
+        Some(false) if !labeled => notify_conflict(ctx, &e.issue, config).await,
+        Some(true) if labeled => notify_resolved(ctx, &e.issue, config).await,
+        // Either GitHub hasn't computed it yet, or nothing changed.
+        _ => Ok(()),
+    }
+}
+
+async fn notify_conflict(
+    ctx: &Context,
+    issue: &Issue,
+    config: &ConflictNotifyConfig,
+) -> anyhow::Result<()> {
+    log::info!("{} has merge conflicts", issue.global_id());
+    issue
+        .add_labels(
+            &ctx.github,
+            vec![Label {
+                name: config.label.clone(),
+            }],
+        )
+        .await?;
+    let comment = issue
+        .create_comment(
+            &ctx.github,
+            &render_template(&config.comment_template, &issue.user.login),
+        )
+        .await?;
+    record_conflict_notification(
+        &*ctx.db.get().await,
+        &issue.repository().to_string(),
+        issue.number,
+        comment.id,
+    )
+    .await
+}
+
+async fn notify_resolved(
+    ctx: &Context,
+    issue: &Issue,
+    config: &ConflictNotifyConfig,
+) -> anyhow::Result<()> {
+    log::info!("{} no longer has merge conflicts", issue.global_id());
+    issue.remove_label(&ctx.github, &config.label).await?;
+    let notified = take_conflict_notification(
+        &*ctx.db.get().await,
+        &issue.repository().to_string(),
+        issue.number,
+    )
+    .await?;
+    if notified.is_some() {
+        issue
+            .post_comment(
+                &ctx.github,
+                &format!(
+                    "@{} the merge conflict has been resolved.",
+                    issue.user.login
+                ),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+fn render_template(template: &str, author: &str) -> String {
+    template.replace("{author}", author)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn author_is_substituted() {
+        assert_eq!(
+            render_template("@{author}, please rebase. Thanks {author}!", "octocat"),
+            "@octocat, please rebase. Thanks octocat!"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Weekly digest of merged pull requests, grouped by author.
+//!
+//! Every week [`ContributorDigestJob`] collects the PRs merged in each
+//! registered repository over the past seven days, posts a summary on the
+//! configured digest issue, and stores it in the `contributor_digests` table.
+
+use crate::{
+    config::{self, ContributorDigestConfig},
+    db::{
+        client::DbClient,
+        jobs::{comment_already_posted, record_posted_comment},
+        registered_repos::registered_repos,
+    },
+    github::{Event, GithubClient, Issue, Repository},
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use tokio_postgres::types::Json;
+use tracing as log;
+
+const FEATURE: &str = "contributor-digest";
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Digest {
+    authors: Vec<AuthorActivity>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct AuthorActivity {
+    login: String,
+    prs: Vec<u64>,
+    /// Whether these are the author's first merged PRs in the repository.
+    new_contributor: bool,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &ContributorDigestConfig,
+) -> anyhow::Result<()> {
+    super::register_repo(ctx, FEATURE, &event.repo().full_name).await
+}
+
+pub struct ContributorDigestJob;
+
+#[async_trait]
+impl Job for ContributorDigestJob {
+    fn name(&self) -> &'static str {
+        "contributor_digest"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let repos = registered_repos(&*db, FEATURE).await?;
+        let week_start = (Utc::now() - Duration::days(7)).date_naive();
+
+        for repo_name in repos {
+            if let Err(e) = digest_repo(ctx, &*db, &repo_name, week_start).await {
+                log::error!("failed to post contributor digest for {repo_name}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn digest_repo(
+    ctx: &Context,
+    db: &impl DbClient,
+    repo_name: &str,
+    week_start: NaiveDate,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = match config::get(&ctx.github, &repo).await {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!("failed to load config for {repo_name}: {e}");
+            return Ok(());
+        }
+    };
+    let Some(config) = config.contributor_digest.as_ref() else {
+        return Ok(());
+    };
+
+    let digest = build_digest(&ctx.github, &repo, week_start).await?;
+    db.execute(
+        "INSERT INTO contributor_digests (week_start, repo, data) VALUES ($1, $2, $3) \
+         ON CONFLICT (repo, week_start) DO UPDATE SET data = EXCLUDED.data",
+        &[&week_start, &repo_name, &Json(&digest)],
+    )
+    .await
+    .context("storing contributor digest")?;
+
+    let key = format!("contributor_digest:{repo_name}:{week_start}");
+    if comment_already_posted(db, &key).await? {
+        return Ok(());
+    }
+    let issue = repo
+        .get_issue_by_number(&ctx.github, config.digest_issue_number)
+        .await?;
+    issue
+        .post_comment(&ctx.github, &format_digest(week_start, &digest))
+        .await?;
+    record_posted_comment(db, &key).await
+}
+
+async fn build_digest(
+    gh: &GithubClient,
+    repo: &Repository,
+    week_start: NaiveDate,
+) -> anyhow::Result<Digest> {
+    let repo_term = format!("repo:{}", repo.full_name);
+    let merged_term = format!("merged:>={week_start}");
+    let mut merged: Vec<Issue> = Vec::new();
+    let mut page = 1;
+    loop {
+        let result = gh
+            .search_issues(&["is:pr", "is:merged", &repo_term, &merged_term], page)
+            .await?;
+        let done = result.items.len() < 100;
+        merged.extend(result.items);
+        if done || merged.len() as u64 >= result.total_count {
+            break;
+        }
+        page += 1;
+    }
+
+    let mut by_author: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    for pr in &merged {
+        by_author
+            .entry(pr.user.login.clone())
+            .or_default()
+            .push(pr.number);
+    }
+
+    // Someone whose merged PRs all landed this week is a new contributor.
+    let queries: Vec<_> = by_author
+        .keys()
+        .map(|login| format!("is:pr is:merged {repo_term} author:{login}"))
+        .collect();
+    let totals = gh.search_issue_counts(&queries).await?;
+    let mut authors: Vec<_> = by_author
+        .into_iter()
+        .zip(totals)
+        .map(|((login, prs), total)| AuthorActivity {
+            new_contributor: total <= prs.len() as u64,
+            login,
+            prs,
+        })
+        .collect();
+    // Most active contributors first.
+    authors.sort_by(|a, b| b.prs.len().cmp(&a.prs.len()).then(a.login.cmp(&b.login)));
+    Ok(Digest { authors })
+}
+
+fn format_digest(week_start: NaiveDate, digest: &Digest) -> String {
+    let mut out = format!("## Contributor digest for the week of {week_start}\n\n");
+    if digest.authors.is_empty() {
+        out.push_str("No pull requests were merged this week.\n");
+        return out;
+    }
+    let total: usize = digest.authors.iter().map(|a| a.prs.len()).sum();
+    let _ = writeln!(
+        out,
+        "{total} pull requests were merged by {} contributors.\n",
+        digest.authors.len()
+    );
+
+    let new: Vec<_> = digest
+        .authors
+        .iter()
+        .filter(|a| a.new_contributor)
+        .collect();
+    if !new.is_empty() {
+        out.push_str("### Welcome new contributors\n\n");
+        for author in new {
+            let _ = writeln!(out, "- {}", author.login);
+        }
+        out.push('\n');
+    }
+
+    out.push_str("| Contributor | Merged PRs |\n|-|-|\n");
+    for author in &digest.authors {
+        let prs = author
+            .prs
+            .iter()
+            .map(|n| format!("#{n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(out, "| {} | {} ({}) |", author.login, author.prs.len(), prs);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_lists_new_contributors() {
+        let digest = Digest {
+            authors: vec![
+                AuthorActivity {
+                    login: "alice".into(),
+                    prs: vec![1, 2],
+                    new_contributor: false,
+                },
+                AuthorActivity {
+                    login: "bob".into(),
+                    prs: vec![3],
+                    new_contributor: true,
+                },
+            ],
+        };
+        let week = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let out = format_digest(week, &digest);
+        assert!(out.contains("3 pull requests were merged by 2 contributors."));
+        assert!(out.contains("### Welcome new contributors\n\n- bob\n"));
+        assert!(out.contains("| alice | 2 (#1, #2) |"));
+    }
+}
@@ -0,0 +1,203 @@
+//! Requests crater runs of pull requests.
+//!
+//! `@rustbot crater <mode>` on a PR, from a member of one of the
+//! `allowed-teams`, opens an issue in `crater-repo` describing the run and
+//! records it in `crater_requests`. When that issue is closed, the PR gets a
+//! comment saying the run is done.
+//!
+//! Parsing is done in the `parser::command::crater` module.
+
+use crate::{
+    config::CraterConfig,
+    db::client::DbClient,
+    github::{self, Event, Issue, IssuesAction},
+    handlers::Context,
+    interactions::ErrorComment,
+};
+use anyhow::Context as _;
+use parser::command::crater::CraterCommand;
+use tracing as log;
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &CraterConfig,
+    event: &Event,
+    cmd: CraterCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        ErrorComment::new(issue, "Crater runs can only be requested on pull requests.")
+            .post(&ctx.github)
+            .await?;
+        return Ok(());
+    }
+    let user = &event.user().login;
+    if !is_allowed(ctx, config, user).await? {
+        ErrorComment::new(
+            issue,
+            format!(
+                "Only members of {} may request crater runs.",
+                config.allowed_teams.join(", ")
+            ),
+        )
+        .post(&ctx.github)
+        .await?;
+        return Ok(());
+    }
+
+    let source_repo = issue.repository().to_string();
+    let crater_issue = ctx
+        .github
+        .repository(&config.crater_repo)
+        .await?
+        .new_issue(
+            &ctx.github,
+            &format!("Crater run for {source_repo}#{}", issue.number),
+            &request_body(issue, &source_repo, &cmd.mode, user),
+            &[],
+        )
+        .await?;
+    log::info!(
+        "requested a {} crater run of {} in {}#{}",
+        cmd.mode,
+        issue.global_id(),
+        config.crater_repo,
+        crater_issue.number
+    );
+    record_request(
+        &*ctx.db.get().await,
+        &source_repo,
+        issue.number,
+        &config.crater_repo,
+        crater_issue.number,
+        user,
+    )
+    .await?;
+    issue
+        .post_comment(
+            &ctx.github,
+            &format!("Crater run requested: {}", crater_issue.html_url),
+        )
+        .await
+}
+
+/// Reports back on the PR when a crater request issue is closed.
+///
+/// This runs for every repository, since the crater repository doesn't need
+/// its own configuration. Request issues are always opened by triagebot, so
+/// only those are looked up in the database.
+pub(super) async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if e.action != IssuesAction::Closed || e.issue.is_pr() || e.issue.user.login != ctx.username {
+        return Ok(());
+    }
+    let Some((source_repo, source_pr)) = complete_request(
+        &*ctx.db.get().await,
+        &e.repository.full_name,
+        e.issue.number,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    ctx.github
+        .repository(&source_repo)
+        .await?
+        .get_issue_by_number(&ctx.github, source_pr)
+        .await?
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "The crater run for this PR has finished, see {} for the results.",
+                e.issue.html_url
+            ),
+        )
+        .await
+}
+
+async fn is_allowed(ctx: &Context, config: &CraterConfig, user: &str) -> anyhow::Result<bool> {
+    for team in &config.allowed_teams {
+        match github::get_team(&ctx.github, team).await? {
+            Some(team) if team.members.iter().any(|m| m.github == user) => return Ok(true),
+            Some(_) => {}
+            None => log::warn!("crater team {team} does not exist"),
+        }
+    }
+    Ok(false)
+}
+
+fn request_body(pr: &Issue, source_repo: &str, mode: &str, requested_by: &str) -> String {
+    format!(
+        "Crater run requested by @{requested_by}.\n\
+         \n\
+         - PR: {source_repo}#{} ({})\n\
+         - Experiment mode: `{mode}`\n",
+        pr.number, pr.html_url
+    )
+}
+
+async fn record_request(
+    db: &impl DbClient,
+    source_repo: &str,
+    source_pr: u64,
+    crater_repo: &str,
+    crater_issue: u64,
+    requested_by: &str,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO crater_requests \
+         (source_repo, source_pr, crater_repo, crater_issue, requested_by, status) \
+         VALUES ($1, $2, $3, $4, $5, 'requested')",
+        &[
+            &source_repo,
+            &(source_pr as i64),
+            &crater_repo,
+            &(crater_issue as i64),
+            &requested_by,
+        ],
+    )
+    .await
+    .context("recording crater request")?;
+    Ok(())
+}
+
+/// Marks the request tracked by the given crater issue as completed,
+/// returning the PR it was made for.
+async fn complete_request(
+    db: &impl DbClient,
+    crater_repo: &str,
+    crater_issue: u64,
+) -> anyhow::Result<Option<(String, u64)>> {
+    let rows = db
+        .query(
+            "UPDATE crater_requests SET status = 'completed' \
+             WHERE crater_repo = $1 AND crater_issue = $2 AND status = 'requested' \
+             RETURNING source_repo, source_pr",
+            &[&crater_repo, &(crater_issue as i64)],
+        )
+        .await
+        .context("completing crater request")?;
+    Ok(rows
+        .first()
+        .map(|row| (row.get(0), row.get::<_, i64>(1) as u64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::IssueBuilder;
+
+    #[test]
+    fn renders_request_body() {
+        let pr = IssueBuilder::new("rust-lang/rust", 123).pr().build();
+        assert_eq!(
+            request_body(&pr, "rust-lang/rust", "check-only", "reviewer"),
+            "Crater run requested by @reviewer.\n\
+             \n\
+             - PR: rust-lang/rust#123 (https://github.com/rust-lang/rust/pull/123)\n\
+             - Experiment mode: `check-only`\n"
+        );
+    }
+}
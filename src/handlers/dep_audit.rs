@@ -0,0 +1,339 @@
+//! Opens issues for dependencies with known security advisories.
+//!
+//! Every week [`DependencyAuditJob`] reads the `Cargo.lock` on each registered
+//! repository's default branch and looks up its crates.io packages in the
+//! [OSV](https://osv.dev) database, which mirrors the RustSec advisory
+//! database. An issue is opened for each RustSec advisory that affects a
+//! locked version, and the advisory is recorded in `reported_advisories` so it
+//! is only reported once per repository.
+
+use crate::{
+    config::{self, DepAuditConfig},
+    db::{client::DbClient, registered_repos::registered_repos},
+    github::Event,
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashSet;
+use tracing as log;
+
+const OSV_API: &str = "https://api.osv.dev/v1";
+/// The most queries OSV accepts in a single batch.
+const OSV_BATCH_SIZE: usize = 1000;
+
+const FEATURE: &str = "dep-audit";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &DepAuditConfig,
+) -> anyhow::Result<()> {
+    super::register_repo(ctx, FEATURE, &event.repo().full_name).await
+}
+
+pub struct DependencyAuditJob;
+
+#[async_trait]
+impl Job for DependencyAuditJob {
+    fn name(&self) -> &'static str {
+        "dependency_audit"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let repos = registered_repos(&*db, FEATURE).await?;
+
+        for repo_name in repos {
+            if let Err(e) = audit_repo(ctx, &*db, &repo_name).await {
+                log::error!("failed to audit dependencies of {repo_name}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn audit_repo(ctx: &Context, db: &impl DbClient, repo_name: &str) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = config::get(&ctx.github, &repo).await?;
+    let Some(config) = config.dep_audit.as_ref() else {
+        return Ok(());
+    };
+    let Some(lockfile) = ctx
+        .github
+        .raw_file(repo_name, &repo.default_branch, "Cargo.lock")
+        .await?
+    else {
+        return Ok(());
+    };
+    let packages = crates_io_packages(std::str::from_utf8(&lockfile)?)?;
+
+    let mut reported: HashSet<String> = db
+        .query(
+            "SELECT advisory_id FROM reported_advisories WHERE repo = $1",
+            &[&repo_name],
+        )
+        .await
+        .context("selecting reported advisories")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    let client = ctx.github.raw();
+    for chunk in packages.chunks(OSV_BATCH_SIZE) {
+        let ids = query_osv(client, chunk).await?;
+        for (package, advisories) in chunk.iter().zip(ids) {
+            // Two versions of a crate can be affected by the same advisory,
+            // which is only reported for the first one.
+            for id in advisories {
+                if !id.starts_with("RUSTSEC-") || reported.contains(&id) {
+                    continue;
+                }
+                let advisory: Advisory = client
+                    .get(format!("{OSV_API}/vulns/{id}"))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .with_context(|| format!("fetching advisory {id}"))?;
+                log::info!(
+                    "{repo_name} depends on {} {}, affected by {id}",
+                    package.name,
+                    package.version
+                );
+                repo.new_issue(
+                    &ctx.github,
+                    &format!("{id}: {} {} is vulnerable", package.name, package.version),
+                    &issue_body(package, &advisory),
+                    &config.labels,
+                )
+                .await?;
+                db.execute(
+                    "INSERT INTO reported_advisories (repo, advisory_id) VALUES ($1, $2) \
+                     ON CONFLICT DO NOTHING",
+                    &[&repo_name, &id],
+                )
+                .await
+                .context("recording reported advisory")?;
+                reported.insert(id);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+/// The packages in `lockfile` that come from crates.io. Path and git
+/// dependencies have no advisories to look up.
+fn crates_io_packages(lockfile: &str) -> anyhow::Result<Vec<LockedPackage>> {
+    let lockfile: Lockfile = toml::from_str(lockfile).context("parsing Cargo.lock")?;
+    Ok(lockfile
+        .package
+        .into_iter()
+        .filter(|package| {
+            package.source.as_deref()
+                == Some("registry+https://github.com/rust-lang/crates.io-index")
+        })
+        .collect())
+}
+
+/// Returns the ids of the advisories affecting each package, in order.
+async fn query_osv(
+    client: &reqwest::Client,
+    packages: &[LockedPackage],
+) -> anyhow::Result<Vec<Vec<String>>> {
+    #[derive(Deserialize)]
+    struct Response {
+        results: Vec<QueryResult>,
+    }
+    #[derive(Deserialize)]
+    struct QueryResult {
+        #[serde(default)]
+        vulns: Vec<VulnId>,
+    }
+    #[derive(Deserialize)]
+    struct VulnId {
+        id: String,
+    }
+
+    let queries: Vec<_> = packages
+        .iter()
+        .map(|package| {
+            serde_json::json!({
+                "package": { "ecosystem": "crates.io", "name": package.name },
+                "version": package.version,
+            })
+        })
+        .collect();
+    let response: Response = client
+        .post(format!("{OSV_API}/querybatch"))
+        .json(&serde_json::json!({ "queries": queries }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("querying OSV")?;
+    Ok(response
+        .results
+        .into_iter()
+        .map(|result| result.vulns.into_iter().map(|vuln| vuln.id).collect())
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct Advisory {
+    id: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    affected: Vec<Affected>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Affected {
+    package: AffectedPackage,
+    #[serde(default)]
+    ranges: Vec<AffectedRange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AffectedPackage {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AffectedRange {
+    events: Vec<RangeEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RangeEvent {
+    Introduced(String),
+    Fixed(String),
+    LastAffected(String),
+    Limit(String),
+}
+
+fn issue_body(package: &LockedPackage, advisory: &Advisory) -> String {
+    let mut affected = Vec::new();
+    let mut patched = Vec::new();
+    let ranges = advisory
+        .affected
+        .iter()
+        .filter(|a| a.package.name == package.name)
+        .flat_map(|a| &a.ranges);
+    for range in ranges {
+        let mut bounds = Vec::new();
+        for event in &range.events {
+            match event {
+                RangeEvent::Introduced(v) if v == "0.0.0-0" => {}
+                RangeEvent::Introduced(v) => bounds.push(format!(">= {v}")),
+                RangeEvent::Fixed(v) => {
+                    bounds.push(format!("< {v}"));
+                    patched.push(format!(">= {v}"));
+                }
+                RangeEvent::LastAffected(v) => bounds.push(format!("<= {v}")),
+                RangeEvent::Limit(v) => bounds.push(format!("< {v}")),
+            }
+        }
+        if bounds.is_empty() {
+            affected.push("all versions".to_string());
+        } else {
+            affected.push(bounds.join(", "));
+        }
+    }
+
+    let mut body = format!(
+        "`Cargo.lock` contains `{} {}`, which is affected by \
+         [{id}](https://rustsec.org/advisories/{id}.html)",
+        package.name,
+        package.version,
+        id = advisory.id,
+    );
+    if advisory.summary.is_empty() {
+        body.push_str(".\n");
+    } else {
+        body.push_str(&format!(": {}\n", advisory.summary));
+    }
+    if !affected.is_empty() {
+        body.push_str(&format!(
+            "\nAffected versions: `{}`\n",
+            affected.join("`, `")
+        ));
+    }
+    if patched.is_empty() {
+        body.push_str("\nNo patched version is available yet.\n");
+    } else {
+        body.push_str(&format!("\nPatched versions: `{}`\n", patched.join("`, `")));
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_crates_io_packages() {
+        let lockfile = r#"
+version = 3
+
+[[package]]
+name = "smallvec"
+version = "0.6.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "c4488ae950c49d403731982257768f48fada354a5203fe81f9bb6f43ca9002be"
+
+[[package]]
+name = "triagebot"
+version = "0.1.0"
+dependencies = ["smallvec"]
+"#;
+        let packages = crates_io_packages(lockfile).unwrap();
+        assert_eq!(packages.len(), 1);
+
+        let advisory: Advisory = serde_json::from_value(serde_json::json!({
+            "id": "RUSTSEC-2019-0009",
+            "summary": "Double-free and use-after-free in SmallVec::grow()",
+            "affected": [{
+                "package": { "ecosystem": "crates.io", "name": "smallvec" },
+                "ranges": [{
+                    "type": "SEMVER",
+                    "events": [
+                        { "introduced": "0.6.5" },
+                        { "fixed": "0.6.10" },
+                    ],
+                }],
+            }],
+        }))
+        .unwrap();
+        assert_eq!(
+            issue_body(&packages[0], &advisory),
+            "`Cargo.lock` contains `smallvec 0.6.9`, which is affected by \
+             [RUSTSEC-2019-0009](https://rustsec.org/advisories/RUSTSEC-2019-0009.html): \
+             Double-free and use-after-free in SmallVec::grow()\n\
+             \n\
+             Affected versions: `>= 0.6.5, < 0.6.10`\n\
+             \n\
+             Patched versions: `>= 0.6.10`\n"
+        );
+    }
+}
@@ -34,11 +34,16 @@ impl Job for DocsUpdateJob {
         "docs_update"
     }
 
-    async fn run(
+    async fn run(&self, ctx: &super::Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        self.run_with_result(ctx, metadata).await.map(|_| ())
+    }
+
+    /// Records the PR that was opened, if any.
+    async fn run_with_result(
         &self,
-        _ctx: &super::Context,
+        ctx: &super::Context,
         _metadata: &serde_json::Value,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<serde_json::Value>> {
         // Only run every other week. Doing it every week can be a bit noisy, and
         // (rarely) a PR can take longer than a week to merge (like if there are
         // CI issues). `Schedule` does not allow expressing this, so check it
@@ -53,30 +58,29 @@ impl Job for DocsUpdateJob {
         let weeks = duration.num_weeks();
         if weeks % 2 != 0 {
             tracing::trace!("skipping job, this is an odd week");
-            return Ok(());
+            return Ok(None);
         }
 
         tracing::trace!("starting docs-update");
-        docs_update()
+        let pr = docs_update(&ctx.github)
             .await
             .context("failed to process docs update")?;
-        Ok(())
+        Ok(pr.map(|pr| serde_json::json!({ "pr": pr.number, "url": pr.html_url })))
     }
 }
 
-pub async fn docs_update() -> Result<Option<Issue>> {
-    let gh = GithubClient::new_from_env();
+pub async fn docs_update(gh: &GithubClient) -> Result<Option<Issue>> {
     let dest_repo = gh.repository(DEST_REPO).await?;
     let work_repo = gh.repository(WORK_REPO).await?;
 
-    let updates = get_submodule_updates(&gh, &dest_repo).await?;
+    let updates = get_submodule_updates(gh, &dest_repo).await?;
     if updates.is_empty() {
         tracing::trace!("no updates this week?");
         return Ok(None);
     }
 
-    create_commit(&gh, &dest_repo, &work_repo, &updates).await?;
-    Ok(Some(create_pr(&gh, &dest_repo, &updates).await?))
+    create_commit(gh, &dest_repo, &work_repo, &updates).await?;
+    Ok(Some(create_pr(gh, &dest_repo, &updates).await?))
 }
 
 struct Update {
@@ -201,3 +205,24 @@ async fn create_pr(gh: &GithubClient, dest_repo: &Repository, updates: &[Update]
     tracing::debug!("created PR {}", pr.html_url);
     Ok(pr)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn uses_the_given_client() {
+        // Nothing is mocked, so the first request fails, but it is sent to
+        // the client's API URL.
+        let server = wiremock::MockServer::start().await;
+        let gh = GithubClient::new(
+            "token".to_string(),
+            server.uri(),
+            format!("{}/graphql", server.uri()),
+            server.uri(),
+        );
+        docs_update(&gh).await.unwrap_err();
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].url.path(), "/repos/rust-lang/rust");
+    }
+}
@@ -0,0 +1,228 @@
+//! Tracks which items are ready for the next edition.
+//!
+//! Team members mark the issue they comment on with
+//! `@rustbot edition-compat compatible`, `needs-changes` or `blocked`, which
+//! is recorded in the `edition_compat` table. `@rustbot edition-compat list`
+//! replies with the current status of every item in the repository, and
+//! every week [`EditionSummaryJob`] keeps a comment with the same summary up
+//! to date on the `tracking-issue` of each registered repository.
+//!
+//! Parsing is done in the `parser::command::edition_compat` module.
+
+use crate::{
+    config::{self, EditionTrackingConfig},
+    db::{client::DbClient, registered_repos::registered_repos},
+    github::Event,
+    handlers::Context,
+    interactions::ErrorComment,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use parser::command::edition_compat::{EditionCompatCommand, EditionCompatStatus};
+use std::fmt::Write as _;
+use tracing as log;
+
+const FEATURE: &str = "edition";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &EditionTrackingConfig,
+) -> anyhow::Result<()> {
+    super::register_repo(ctx, FEATURE, &event.repo().full_name).await
+}
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &EditionTrackingConfig,
+    event: &Event,
+    cmd: EditionCompatCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let repo = issue.repository().to_string();
+    let db = ctx.db.get().await;
+    match cmd {
+        EditionCompatCommand::Set(status) => {
+            if !ctx.is_team_member(event.user()).await.unwrap_or(false) {
+                ErrorComment::new(
+                    issue,
+                    "Only team members may set the edition compatibility of an issue.",
+                )
+                .post(&ctx.github)
+                .await?;
+                return Ok(());
+            }
+            set_status(
+                &*db,
+                &repo,
+                &format!("#{}", issue.number),
+                status,
+                &event.user().login,
+            )
+            .await
+        }
+        EditionCompatCommand::List => {
+            let items = get_statuses(&*db, &repo).await?;
+            issue
+                .post_comment(&ctx.github, &summary(&config.next_edition, &items))
+                .await
+        }
+    }
+}
+
+pub struct EditionSummaryJob;
+
+#[async_trait]
+impl Job for EditionSummaryJob {
+    fn name(&self) -> &'static str {
+        "edition_summary"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        for repo_name in registered_repos(&*db, FEATURE).await? {
+            if let Err(e) = update_summary(ctx, &*db, &repo_name).await {
+                log::error!("failed to update the edition summary of {repo_name}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn update_summary(ctx: &Context, db: &impl DbClient, repo_name: &str) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = config::get(&ctx.github, &repo).await?;
+    let Some(config) = config.edition.as_ref() else {
+        return Ok(());
+    };
+
+    let body = summary(&config.next_edition, &get_statuses(db, repo_name).await?);
+    let issue = repo
+        .get_issue_by_number(&ctx.github, config.tracking_issue)
+        .await?;
+    match summary_comment(db, repo_name).await? {
+        Some(comment_id) => issue.edit_comment(&ctx.github, comment_id, &body).await?,
+        None => {
+            let comment = issue.create_comment(&ctx.github, &body).await?;
+            db.execute(
+                "INSERT INTO edition_summary_comments (repo, comment_id) VALUES ($1, $2) \
+                 ON CONFLICT (repo) DO UPDATE SET comment_id = EXCLUDED.comment_id",
+                &[&repo_name, &(comment.id as i64)],
+            )
+            .await
+            .context("recording edition summary comment")?;
+        }
+    }
+    Ok(())
+}
+
+struct ItemStatus {
+    item: String,
+    status: String,
+    updated_by: String,
+}
+
+async fn set_status(
+    db: &impl DbClient,
+    repo: &str,
+    item: &str,
+    status: EditionCompatStatus,
+    updated_by: &str,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO edition_compat (item, repo, status, updated_by, updated_at) \
+         VALUES ($1, $2, $3, $4, now()) \
+         ON CONFLICT (repo, item) DO UPDATE SET status = EXCLUDED.status, \
+         updated_by = EXCLUDED.updated_by, updated_at = EXCLUDED.updated_at",
+        &[&item, &repo, &status.as_str(), &updated_by],
+    )
+    .await
+    .context("setting edition compatibility")?;
+    Ok(())
+}
+
+async fn get_statuses(db: &impl DbClient, repo: &str) -> anyhow::Result<Vec<ItemStatus>> {
+    let rows = db
+        .query(
+            "SELECT item, status, updated_by FROM edition_compat WHERE repo = $1 ORDER BY item",
+            &[&repo],
+        )
+        .await
+        .context("selecting edition compatibility")?;
+    Ok(rows
+        .iter()
+        .map(|row| ItemStatus {
+            item: row.get(0),
+            status: row.get(1),
+            updated_by: row.get(2),
+        })
+        .collect())
+}
+
+async fn summary_comment(db: &impl DbClient, repo: &str) -> anyhow::Result<Option<u64>> {
+    let rows = db
+        .query(
+            "SELECT comment_id FROM edition_summary_comments WHERE repo = $1",
+            &[&repo],
+        )
+        .await
+        .context("selecting edition summary comment")?;
+    Ok(rows.first().map(|row| row.get::<_, i64>(0) as u64))
+}
+
+fn summary(next_edition: &str, items: &[ItemStatus]) -> String {
+    let mut summary = format!("## Edition {next_edition} compatibility\n\n");
+    for status in EditionCompatStatus::ALL {
+        let count = items
+            .iter()
+            .filter(|item| item.status == status.as_str())
+            .count();
+        writeln!(summary, "- `{}`: {count}", status.as_str()).unwrap();
+    }
+    if !items.is_empty() {
+        summary.push_str("\n| Item | Status | Updated by |\n|---|---|---|\n");
+        for item in items {
+            writeln!(
+                summary,
+                "| {} | `{}` | {} |",
+                item.item, item.status, item.updated_by
+            )
+            .unwrap();
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_statuses() {
+        let item = |item: &str, status: &str| ItemStatus {
+            item: item.to_string(),
+            status: status.to_string(),
+            updated_by: "ferris".to_string(),
+        };
+        let items = [
+            item("#1", "compatible"),
+            item("#2", "blocked"),
+            item("#3", "compatible"),
+        ];
+        assert_eq!(
+            summary("2024", &items),
+            "## Edition 2024 compatibility\n\
+             \n\
+             - `compatible`: 2\n\
+             - `needs-changes`: 0\n\
+             - `blocked`: 1\n\
+             \n\
+             | Item | Status | Updated by |\n\
+             |---|---|---|\n\
+             | #1 | `compatible` | ferris |\n\
+             | #2 | `blocked` | ferris |\n\
+             | #3 | `compatible` | ferris |\n"
+        );
+    }
+}
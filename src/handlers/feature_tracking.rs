@@ -0,0 +1,211 @@
+//! Support for unstable feature tracking issues.
+//!
+//! When an issue gets the tracking issue label, a checklist of the
+//! stabilization steps is posted and the issue is recorded in the
+//! `tracking_issues` table. The feature name is extracted from the title with
+//! the configured `feature-pattern`, which must have a `feature` capture group.
+//!
+//! [`FeatureTrackingJob`] runs weekly and pings the configured team on
+//! tracking issues that have been open for over a year without any recent
+//! activity.
+
+use crate::{
+    config::{self, FeatureTrackingConfig},
+    db::client::DbClient,
+    github::{IssueState, IssuesAction, IssuesEvent},
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use regex::Regex;
+use tracing as log;
+
+/// Tracking issues updated more recently than this are left alone.
+const STALE_AFTER_DAYS: i64 = 90;
+
+pub(super) struct FeatureTrackingInput {
+    feature_name: Option<String>,
+}
+
+pub(super) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&FeatureTrackingConfig>,
+) -> Result<Option<FeatureTrackingInput>, String> {
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    match &event.action {
+        IssuesAction::Labeled { label } if label.name == config.label => {}
+        _ => return Ok(None),
+    }
+    if event.issue.is_pr() {
+        return Ok(None);
+    }
+
+    let pattern = Regex::new(&config.feature_pattern)
+        .map_err(|e| format!("invalid feature tracking `feature-pattern`: {e}"))?;
+    Ok(Some(FeatureTrackingInput {
+        feature_name: feature_name(&pattern, &event.issue.title),
+    }))
+}
+
+pub(super) async fn handle_input(
+    ctx: &Context,
+    config: &FeatureTrackingConfig,
+    event: &IssuesEvent,
+    input: FeatureTrackingInput,
+) -> anyhow::Result<()> {
+    let db = ctx.db.get().await;
+    let inserted = insert_tracking_issue(
+        &*db,
+        &event.repository.full_name,
+        event.issue.number,
+        input.feature_name.as_deref(),
+    )
+    .await?;
+    // Relabelling an issue that is already tracked shouldn't post the
+    // checklist a second time.
+    if !inserted {
+        return Ok(());
+    }
+
+    let feature = input.feature_name.as_deref().unwrap_or("this feature");
+    let checklist = config.checklist.replace("{feature}", feature);
+    event.issue.post_comment(&ctx.github, &checklist).await?;
+    Ok(())
+}
+
+fn feature_name(pattern: &Regex, title: &str) -> Option<String> {
+    Some(
+        pattern
+            .captures(title)?
+            .name("feature")?
+            .as_str()
+            .to_string(),
+    )
+}
+
+async fn insert_tracking_issue(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    feature_name: Option<&str>,
+) -> anyhow::Result<bool> {
+    let rows = db
+        .execute(
+            "INSERT INTO tracking_issues (repo, issue_number, feature_name, created_at) \
+             VALUES ($1, $2, $3, now()) \
+             ON CONFLICT (repo, issue_number) DO NOTHING",
+            &[&repo, &(issue_number as i64), &feature_name],
+        )
+        .await
+        .context("inserting tracking issue")?;
+    Ok(rows > 0)
+}
+
+pub struct FeatureTrackingJob;
+
+#[async_trait]
+impl Job for FeatureTrackingJob {
+    fn name(&self) -> &'static str {
+        "feature_tracking"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let rows = db
+            .query(
+                "SELECT repo, issue_number, feature_name FROM tracking_issues \
+                 WHERE stabilized_at IS NULL AND created_at < now() - INTERVAL '1 year'",
+                &[],
+            )
+            .await
+            .context("selecting old tracking issues")?;
+
+        for row in rows {
+            let repo_name: String = row.get(0);
+            let issue_number: i64 = row.get(1);
+            let feature_name: Option<String> = row.get(2);
+            if let Err(e) =
+                check_tracking_issue(ctx, &*db, &repo_name, issue_number, feature_name).await
+            {
+                log::error!("failed to check tracking issue {repo_name}#{issue_number}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn check_tracking_issue(
+    ctx: &Context,
+    db: &impl DbClient,
+    repo_name: &str,
+    issue_number: i64,
+    feature_name: Option<String>,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = match config::get(&ctx.github, &repo).await {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!("failed to load config for {repo_name}: {e}");
+            return Ok(());
+        }
+    };
+    let Some(config) = config.feature_tracking.as_ref() else {
+        return Ok(());
+    };
+
+    let issue = repo
+        .get_issue_by_number(&ctx.github, issue_number as u64)
+        .await?;
+    if issue.state == IssueState::Closed {
+        db.execute(
+            "UPDATE tracking_issues SET stabilized_at = now() \
+             WHERE repo = $1 AND issue_number = $2",
+            &[&repo_name, &issue_number],
+        )
+        .await
+        .context("marking tracking issue as done")?;
+        return Ok(());
+    }
+    if Utc::now() - issue.updated_at < Duration::days(STALE_AFTER_DAYS) {
+        return Ok(());
+    }
+
+    let feature = feature_name.as_deref().unwrap_or("this feature");
+    issue
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "This tracking issue for `{feature}` has been open for over a year \
+                 and has not seen any activity in the last {STALE_AFTER_DAYS} days. \
+                 Is it ready for stabilization, or should it be removed?\n\n\
+                 cc {}",
+                config.ping
+            ),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_feature_name_from_title() {
+        let pattern = Regex::new(&FeatureTrackingConfig::feature_pattern_default()).unwrap();
+        assert_eq!(
+            feature_name(&pattern, "Tracking Issue for `let_chains`"),
+            Some("let_chains".to_string())
+        );
+        assert_eq!(
+            feature_name(&pattern, "Tracking issue for feature(never_type)"),
+            Some("never_type".to_string())
+        );
+        assert_eq!(feature_name(&pattern, "ICE in borrowck"), None);
+    }
+}
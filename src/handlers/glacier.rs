@@ -13,11 +13,7 @@ pub(super) async fn handle_command(
     event: &Event,
     cmd: GlacierCommand,
 ) -> anyhow::Result<()> {
-    let is_team_member = event
-        .user()
-        .is_team_member(&ctx.github)
-        .await
-        .unwrap_or(false);
+    let is_team_member = ctx.is_team_member(event.user()).await.unwrap_or(false);
 
     if !is_team_member {
         return Ok(());
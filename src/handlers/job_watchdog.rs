@@ -0,0 +1,71 @@
+//! Alerts operators about jobs the scheduler hasn't gotten to.
+//!
+//! Every [`CHECK_INTERVAL`], [`check_overdue_jobs`] looks for jobs that
+//! still haven't run an hour after their scheduled time, which means the job
+//! runner is behind (e.g. because other jobs keep filling the queue) or
+//! stuck. Each such job is reported once to the Zulip stream in
+//! `TRIAGEBOT_WATCHDOG_ZULIP_STREAM`, or only logged if that isn't set, and
+//! marked with `overdue_alerted_at` in the `jobs` table.
+//!
+//! The check runs on a timer of its own rather than as a job, since it has
+//! to keep working when the job queue doesn't.
+
+use crate::{
+    db::jobs::{get_overdue_jobs, record_overdue_alert},
+    handlers::Context,
+    zulip::{MessageApiRequest, Recipient},
+};
+use chrono::Duration;
+use std::fmt::Write as _;
+use tracing as log;
+
+/// How often [`check_overdue_jobs`] runs.
+pub const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+/// Reports the jobs that are more than an hour overdue and haven't been
+/// reported yet.
+pub async fn check_overdue_jobs(ctx: &Context) -> anyhow::Result<()> {
+    let db = ctx.db.get().await;
+    let mut message = String::new();
+    let mut reported = Vec::new();
+    for job in get_overdue_jobs(&*db, Duration::hours(1)).await? {
+        log::error!(
+            "job {} ({}) scheduled at {} hasn't run yet",
+            job.id,
+            job.name,
+            job.scheduled_at
+        );
+        writeln!(
+            message,
+            "- `{}` (`{}`), scheduled at {}",
+            job.name, job.id, job.scheduled_at
+        )
+        .unwrap();
+        reported.push(job.id);
+    }
+    if reported.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(stream) = std::env::var("TRIAGEBOT_WATCHDOG_ZULIP_STREAM")
+        .ok()
+        .and_then(|id| id.parse().ok())
+    {
+        let content = format!(
+            "These jobs are more than an hour overdue, the job runner may be stuck:\n{message}"
+        );
+        MessageApiRequest {
+            recipient: Recipient::Stream {
+                id: stream,
+                topic: "overdue jobs",
+            },
+            content: &content,
+        }
+        .send(ctx.github.raw())
+        .await?;
+    }
+    for id in reported {
+        record_overdue_alert(&*db, &id).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Applies labels to newly opened issues based on keywords found in their
+//! title or body.
+//!
+//! Configuration is done with one `[[keyword-label]]` table per rule:
+//!
+//! ```toml
+//! [[keyword-label]]
+//! keywords = ["ice", "internal compiler error"]
+//! label = "I-ICE"
+//! match-body = false
+//! ```
+
+use crate::{
+    config::KeywordLabelConfig,
+    github::{IssuesAction, IssuesEvent, Label, UnknownLabels},
+    handlers::Context,
+};
+use tracing as log;
+
+pub(super) struct KeywordLabelInput {
+    labels: Vec<Label>,
+}
+
+pub(super) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&Vec<KeywordLabelConfig>>,
+) -> Result<Option<KeywordLabelInput>, String> {
+    let rules = match config {
+        Some(rules) => rules,
+        None => return Ok(None),
+    };
+    if event.action != IssuesAction::Opened {
+        return Ok(None);
+    }
+
+    let body = event.issue.body.as_str();
+    let mut labels: Vec<Label> = Vec::new();
+    for rule in rules {
+        let matched = (rule.match_title
+            && contains_any_keyword(&event.issue.title, &rule.keywords))
+            || (rule.match_body && contains_any_keyword(body, &rule.keywords));
+        let already_applied = event.issue.labels().iter().any(|l| l.name == rule.label)
+            || labels.iter().any(|l| l.name == rule.label);
+        if matched && !already_applied {
+            labels.push(Label {
+                name: rule.label.clone(),
+            });
+        }
+    }
+
+    if labels.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(KeywordLabelInput { labels }))
+}
+
+pub(super) async fn handle_input(
+    ctx: &Context,
+    _config: &Vec<KeywordLabelConfig>,
+    event: &IssuesEvent,
+    input: KeywordLabelInput,
+) -> anyhow::Result<()> {
+    // Labels are added one at a time so that a rule naming a missing label
+    // doesn't keep the other rules' labels from being applied.
+    for label in input.labels {
+        if let Err(e) = event.issue.add_labels(&ctx.github, vec![label]).await {
+            if let Some(err @ UnknownLabels { .. }) = e.downcast_ref() {
+                log::warn!(
+                    "keyword label rules for {} refer to missing labels: {}",
+                    event.repository.full_name,
+                    err
+                );
+                continue;
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether any of `keywords` occurs anywhere in `text`, ignoring
+/// case.
+fn contains_any_keyword(text: &str, keywords: &[String]) -> bool {
+    let text = text.to_lowercase();
+    keywords
+        .iter()
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .any(|keyword| text.contains(&keyword))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keywords(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn keywords_match_inside_words() {
+        let kw = keywords(&["ice"]);
+        assert!(contains_any_keyword("ices", &kw));
+        assert!(contains_any_keyword("rustc ICE: index out of bounds", &kw));
+        assert!(contains_any_keyword("(ice)", &kw));
+        assert!(!contains_any_keyword("i c e", &kw));
+    }
+
+    #[test]
+    fn matching_ignores_case() {
+        let kw = keywords(&["Internal Compiler Error"]);
+        assert!(contains_any_keyword(
+            "error: INTERNAL COMPILER ERROR: unexpected panic",
+            &kw
+        ));
+        assert!(!contains_any_keyword("internal error", &kw));
+    }
+
+    #[test]
+    fn any_keyword_in_rule_matches() {
+        let kw = keywords(&["segfault", "sigsegv", "crash"]);
+        assert!(contains_any_keyword("process died with SIGSEGV", &kw));
+        assert!(contains_any_keyword("The compiler will crash", &kw));
+        assert!(!contains_any_keyword("everything works", &kw));
+        assert!(!contains_any_keyword("anything", &keywords(&[])));
+    }
+}
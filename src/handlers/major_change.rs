@@ -204,12 +204,7 @@ pub(super) async fn handle_command(
         return Ok(());
     }
 
-    let is_team_member = event
-        .user()
-        .is_team_member(&ctx.github)
-        .await
-        .ok()
-        .unwrap_or(false);
+    let is_team_member = ctx.is_team_member(event.user()).await.ok().unwrap_or(false);
 
     if !is_team_member {
         let cmnt = ErrorComment::new(&issue, "Only team members can second issues.");
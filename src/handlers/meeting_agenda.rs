@@ -0,0 +1,205 @@
+//! Builds meeting agendas from nominated issues.
+//!
+//! [`MeetingAgendaJob`] periodically checks each registered repository and,
+//! whenever its configured cron `schedule` has fired since the last agenda,
+//! posts a checklist of all open `{team}-nominated` issues on the agenda
+//! issue, replacing the previous agenda comment.
+
+use crate::{
+    config::{self, MeetingAgendaConfig},
+    db::client::DbClient,
+    github::{Event, Issue, Query},
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use std::fmt::Write;
+use std::str::FromStr;
+use tracing as log;
+
+/// Maximum number of characters of an issue body shown in the agenda.
+const EXCERPT_LEN: usize = 200;
+
+const FEATURE: &str = "meeting-agenda";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &MeetingAgendaConfig,
+) -> anyhow::Result<()> {
+    super::register_repo(ctx, FEATURE, &event.repo().full_name).await
+}
+
+pub struct MeetingAgendaJob;
+
+#[async_trait]
+impl Job for MeetingAgendaJob {
+    fn name(&self) -> &'static str {
+        "meeting_agenda"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let rows = db
+            .query(
+                "SELECT r.repo, a.bot_comment_id, COALESCE(a.last_run_at, r.registered_at) \
+                 FROM registered_repos r LEFT JOIN meeting_agendas a ON a.repo = r.repo \
+                 WHERE r.feature = $1",
+                &[&FEATURE],
+            )
+            .await
+            .context("selecting meeting agendas")?;
+
+        for row in rows {
+            let repo_name: String = row.get(0);
+            let bot_comment_id: Option<i64> = row.get(1);
+            let last_run_at: DateTime<Utc> = row.get(2);
+            if let Err(e) = post_agenda(ctx, &*db, &repo_name, bot_comment_id, last_run_at).await {
+                log::error!("failed to post the meeting agenda for {repo_name}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn post_agenda(
+    ctx: &Context,
+    db: &impl DbClient,
+    repo_name: &str,
+    bot_comment_id: Option<i64>,
+    last_run_at: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = match config::get(&ctx.github, &repo).await {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!("failed to load config for {repo_name}: {e}");
+            return Ok(());
+        }
+    };
+    let Some(config) = config.meeting_agenda.as_ref() else {
+        return Ok(());
+    };
+    let schedule = match Schedule::from_str(&config.schedule) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            log::warn!("invalid meeting agenda schedule for {repo_name}: {e}");
+            return Ok(());
+        }
+    };
+    if !is_due(&schedule, last_run_at, Utc::now()) {
+        return Ok(());
+    }
+
+    let label = format!("{}-nominated", config.team);
+    let nominated = repo
+        .get_issues(
+            &ctx.github,
+            &Query {
+                filters: vec![("state", "open")],
+                include_labels: vec![&label],
+                exclude_labels: vec![],
+            },
+        )
+        .await?;
+    let agenda_issue = repo
+        .get_issue_by_number(&ctx.github, config.agenda_issue_number)
+        .await?;
+    // The previous agenda is only removed once the new one is up, so the
+    // team isn't left without one if posting fails.
+    let comment = agenda_issue
+        .create_comment(&ctx.github, &format_agenda(&config.team, &nominated))
+        .await?;
+    if let Some(id) = bot_comment_id {
+        if let Err(e) = agenda_issue.delete_comment(&ctx.github, id as u64).await {
+            log::warn!("failed to delete previous agenda in {repo_name}: {e:?}");
+        }
+    }
+
+    db.execute(
+        "INSERT INTO meeting_agendas (repo, bot_comment_id, last_run_at) \
+         VALUES ($1, $2, now()) ON CONFLICT (repo) DO UPDATE \
+         SET bot_comment_id = EXCLUDED.bot_comment_id, last_run_at = EXCLUDED.last_run_at",
+        &[&repo_name, &(comment.id as i64)],
+    )
+    .await
+    .context("updating meeting agenda")?;
+    Ok(())
+}
+
+/// Whether `schedule` fired between the last agenda and `now`.
+fn is_due(schedule: &Schedule, last_run_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    schedule
+        .after(&last_run_at)
+        .next()
+        .map_or(false, |next| next <= now)
+}
+
+fn format_agenda(team: &str, issues: &[Issue]) -> String {
+    let mut agenda = format!("## {team} meeting agenda\n\n");
+    if issues.is_empty() {
+        agenda.push_str("No issues are nominated this week.\n");
+        return agenda;
+    }
+    for issue in issues {
+        let _ = writeln!(
+            agenda,
+            "- [ ] [{}]({}) by @{}",
+            issue.title, issue.html_url, issue.user.login
+        );
+        let excerpt = excerpt(&issue.body);
+        if !excerpt.is_empty() {
+            let _ = writeln!(agenda, "  > {excerpt}");
+        }
+    }
+    agenda
+}
+
+/// The first non-empty line of `body`, shortened to [`EXCERPT_LEN`] characters.
+fn excerpt(body: &str) -> String {
+    let line = body
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if line.chars().count() > EXCERPT_LEN {
+        let mut short: String = line.chars().take(EXCERPT_LEN).collect();
+        short.push('…');
+        short
+    } else {
+        line.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_once_schedule_fired() {
+        // Mondays at 10:00 UTC.
+        let schedule = Schedule::from_str("0 0 10 * * Mon *").unwrap();
+        let last = "2024-01-01T10:00:00Z".parse().unwrap();
+        assert!(!is_due(
+            &schedule,
+            last,
+            "2024-01-05T12:00:00Z".parse().unwrap()
+        ));
+        assert!(is_due(
+            &schedule,
+            last,
+            "2024-01-08T10:00:00Z".parse().unwrap()
+        ));
+    }
+
+    #[test]
+    fn excerpt_is_first_line_and_truncated() {
+        assert_eq!(excerpt("\n\n  Hello there\nmore"), "Hello there");
+        let long = "a".repeat(250);
+        assert_eq!(excerpt(&long).chars().count(), EXCERPT_LEN + 1);
+        assert_eq!(excerpt(""), "");
+    }
+}
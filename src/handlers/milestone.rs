@@ -0,0 +1,168 @@
+//! Assigns merged pull requests to a milestone based on a version label.
+//!
+//! The `label-pattern` is a regular expression with a `version` capture
+//! group, e.g. `^relnotes-(?P<version>\d+\.\d+)$`. When a PR carrying a
+//! matching label is merged, the captured version is substituted into
+//! `milestone-match` (e.g. `"{version}.0"`) and the PR is assigned to the
+//! open milestone with that title. If none exists, the
+//! `needs-milestone-label` is applied instead.
+
+use crate::{
+    config::MilestoneConfig,
+    github::{IssuesAction, IssuesEvent, Label, Milestone, Repository},
+    handlers::Context,
+};
+use regex::Regex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long the list of open milestones of a repository is reused.
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Open milestones per repository, shared through the [`Context`].
+#[derive(Default)]
+pub struct MilestoneCache {
+    entries: HashMap<String, (Instant, Vec<Milestone>)>,
+}
+
+impl MilestoneCache {
+    fn get(&self, repo: &str) -> Option<Vec<Milestone>> {
+        self.entries
+            .get(repo)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < CACHE_TTL)
+            .map(|(_, milestones)| milestones.clone())
+    }
+
+    fn insert(&mut self, repo: &str, milestones: Vec<Milestone>) {
+        self.entries
+            .insert(repo.to_string(), (Instant::now(), milestones));
+    }
+}
+
+pub(super) struct MilestoneInput {
+    /// The milestone title implied by the version label.
+    title: String,
+}
+
+pub(super) async fn parse_input(
+    _ctx: &Context,
+    event: &IssuesEvent,
+    config: Option<&MilestoneConfig>,
+) -> Result<Option<MilestoneInput>, String> {
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    if event.action != IssuesAction::Closed || !event.issue.merged {
+        return Ok(None);
+    }
+    if event.issue.milestone.is_some() {
+        return Ok(None);
+    }
+
+    let pattern = Regex::new(&config.label_pattern)
+        .map_err(|e| format!("invalid milestone `label-pattern`: {e}"))?;
+    Ok(event
+        .issue
+        .labels()
+        .iter()
+        .find_map(|label| implied_milestone(&pattern, &config.milestone_match, &label.name))
+        .map(|title| MilestoneInput { title }))
+}
+
+pub(super) async fn handle_input(
+    ctx: &Context,
+    config: &MilestoneConfig,
+    event: &IssuesEvent,
+    input: MilestoneInput,
+) -> anyhow::Result<()> {
+    let milestones = open_milestones(ctx, &event.repository).await?;
+    match best_match(&milestones, &input.title) {
+        Some(milestone) => {
+            event
+                .repository
+                .set_milestone(&ctx.github, milestone, event.issue.number)
+                .await?;
+        }
+        None => {
+            event
+                .issue
+                .add_labels(
+                    &ctx.github,
+                    vec![Label {
+                        name: config.needs_milestone_label.clone(),
+                    }],
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn open_milestones(ctx: &Context, repo: &Repository) -> anyhow::Result<Vec<Milestone>> {
+    if let Some(milestones) = ctx.milestone_cache.lock().unwrap().get(&repo.full_name) {
+        return Ok(milestones);
+    }
+    let milestones = repo.open_milestones(&ctx.github).await?;
+    ctx.milestone_cache
+        .lock()
+        .unwrap()
+        .insert(&repo.full_name, milestones.clone());
+    Ok(milestones)
+}
+
+/// Returns the milestone title implied by `label`, if it matches `pattern`.
+fn implied_milestone(pattern: &Regex, milestone_match: &str, label: &str) -> Option<String> {
+    let version = pattern.captures(label)?.name("version")?.as_str();
+    Some(milestone_match.replace("{version}", version))
+}
+
+/// Picks the milestone titled `title`, preferring the one due soonest if
+/// several share the same title.
+fn best_match<'a>(milestones: &'a [Milestone], title: &str) -> Option<&'a Milestone> {
+    milestones
+        .iter()
+        .filter(|m| m.title.trim() == title)
+        .min_by_key(|m| (m.due_on.is_none(), m.due_on))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn milestone(title: &str, due_on: Option<&str>) -> Milestone {
+        serde_json::from_value(serde_json::json!({
+            "number": 1,
+            "title": title,
+            "due_on": due_on,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn version_is_extracted_from_label() {
+        let pattern = Regex::new(r"^relnotes-(?P<version>\d+\.\d+)$").unwrap();
+        assert_eq!(
+            implied_milestone(&pattern, "{version}.0", "relnotes-1.75"),
+            Some("1.75.0".to_string())
+        );
+        assert_eq!(implied_milestone(&pattern, "{version}.0", "relnotes"), None);
+        let no_group = Regex::new(r"^beta-nominated$").unwrap();
+        assert_eq!(
+            implied_milestone(&no_group, "{version}", "beta-nominated"),
+            None
+        );
+    }
+
+    #[test]
+    fn best_match_prefers_earliest_due_date() {
+        let milestones = vec![
+            milestone("1.76.0", Some("2024-02-08T00:00:00Z")),
+            milestone("1.75.0", None),
+            milestone("1.75.0", Some("2023-12-28T00:00:00Z")),
+        ];
+        let found = best_match(&milestones, "1.75.0").unwrap();
+        assert!(found.due_on.is_some());
+        assert!(best_match(&milestones, "1.77.0").is_none());
+    }
+}
@@ -69,8 +69,8 @@ pub async fn handle(ctx: &Context, event: &Event) -> anyhow::Result<()> {
             // The webhook timeout of 10 seconds can be too short, so process in
             // the background.
             let diff = cargo.diff.clone();
+            let gh = ctx.github.clone();
             tokio::task::spawn(async move {
-                let gh = GithubClient::new_from_env();
                 if let Err(e) = milestone_cargo(&gh, &version, &diff).await {
                     log::error!("failed to milestone cargo: {e:?}");
                 }
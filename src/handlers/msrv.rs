@@ -0,0 +1,243 @@
+//! Tracks changes to the minimum supported Rust version (MSRV).
+//!
+//! When a PR changes the `rust-version` in the root `Cargo.toml`:
+//!
+//! * If the new version is lower than the version in the configured
+//!   `msrv-file` (a `rust-toolchain.toml` or legacy `rust-toolchain`), the PR
+//!   is labeled `msrv-violation` with a comment explaining why.
+//! * If the version was raised, the PR gets the `msrv-label` and `ping` is
+//!   notified.
+//!
+//! Every version proposed by a PR is recorded in `msrv_history`.
+
+use crate::{
+    config::MsrvConfig,
+    db::client::DbClient,
+    github::{Event, IssuesAction, Label},
+    handlers::Context,
+};
+use anyhow::Context as _;
+use std::cmp::Ordering;
+
+const VIOLATION_LABEL: &str = "msrv-violation";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &MsrvConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !matches!(
+        e.action,
+        IssuesAction::Opened | IssuesAction::Reopened | IssuesAction::Synchronize
+    ) {
+        return Ok(());
+    }
+    let Some(diff) = e.issue.diff(&ctx.github).await? else {
+        return Ok(());
+    };
+    if !diff.iter().any(|file| file.path == "Cargo.toml") {
+        return Ok(());
+    }
+    let (Some(base), Some(head)) = (&e.issue.base, &e.issue.head) else {
+        return Ok(());
+    };
+
+    let old = file(ctx, &base.repo.full_name, &base.sha, "Cargo.toml")
+        .await?
+        .and_then(|toml| rust_version(&toml));
+    let Some(new) = file(ctx, &head.repo.full_name, &head.sha, "Cargo.toml")
+        .await?
+        .and_then(|toml| rust_version(&toml))
+    else {
+        return Ok(());
+    };
+    if old.as_ref() == Some(&new) {
+        return Ok(());
+    }
+
+    record_version(
+        &*ctx.db.get().await,
+        &e.repository.full_name,
+        e.issue.number,
+        &new,
+    )
+    .await?;
+
+    let minimum = file(ctx, &base.repo.full_name, &base.sha, &config.msrv_file)
+        .await?
+        .and_then(|content| toolchain_version(&config.msrv_file, &content));
+    let has_label = |name: &str| e.issue.labels().iter().any(|l| l.name == name);
+
+    if let Some(minimum) = minimum.filter(|min| compare_versions(&new, min) == Ordering::Less) {
+        // Avoid repeating the comment on every push.
+        if has_label(VIOLATION_LABEL) {
+            return Ok(());
+        }
+        e.issue
+            .add_labels(
+                &ctx.github,
+                vec![Label {
+                    name: VIOLATION_LABEL.to_string(),
+                }],
+            )
+            .await?;
+        return e
+            .issue
+            .post_comment(
+                &ctx.github,
+                &format!(
+                    ":warning: This PR sets `rust-version` to `{new}`, which is lower than \
+                     the `{minimum}` required by `{}`.",
+                    config.msrv_file
+                ),
+            )
+            .await;
+    }
+
+    let raised = old.map_or(false, |old| {
+        compare_versions(&new, &old) == Ordering::Greater
+    });
+    if raised && !has_label(&config.msrv_label) {
+        e.issue
+            .add_labels(
+                &ctx.github,
+                vec![Label {
+                    name: config.msrv_label.clone(),
+                }],
+            )
+            .await?;
+        if let Some(ping) = &config.ping {
+            e.issue
+                .post_comment(
+                    &ctx.github,
+                    &format!("This PR raises the MSRV to `{new}`.\n\ncc {ping}"),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn file(ctx: &Context, repo: &str, sha: &str, path: &str) -> anyhow::Result<Option<String>> {
+    let content = ctx.github.raw_file(repo, sha, path).await?;
+    Ok(content.map(|c| String::from_utf8_lossy(&c).into_owned()))
+}
+
+/// The `rust-version` of a `Cargo.toml`, either the package's own or the one
+/// it inherits from the workspace.
+fn rust_version(cargo_toml: &str) -> Option<String> {
+    let manifest: toml::Table = toml::from_str(cargo_toml).ok()?;
+    let package_version = manifest.get("package").and_then(|p| p.get("rust-version"));
+    let workspace_version = manifest
+        .get("workspace")
+        .and_then(|w| w.get("package"))
+        .and_then(|p| p.get("rust-version"));
+    package_version
+        .and_then(|v| v.as_str())
+        .or_else(|| workspace_version?.as_str())
+        .map(str::to_string)
+}
+
+/// The version a toolchain file pins, if it pins a release rather than a
+/// channel like `stable` or `nightly-2024-01-01`.
+fn toolchain_version(path: &str, content: &str) -> Option<String> {
+    let channel = if path.ends_with(".toml") || content.trim_start().starts_with('[') {
+        let toolchain: toml::Table = toml::from_str(content).ok()?;
+        toolchain
+            .get("toolchain")?
+            .get("channel")?
+            .as_str()?
+            .to_string()
+    } else {
+        content.trim().to_string()
+    };
+    parse_version(&channel).map(|_| channel)
+}
+
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let mut parts = [0; 3];
+    let mut components = version.split('.');
+    for (i, part) in parts.iter_mut().enumerate() {
+        match components.next() {
+            Some(component) => *part = component.parse().ok()?,
+            // Only the patch version may be left out.
+            None if i == 2 => {}
+            None => return None,
+        }
+    }
+    components.next().is_none().then_some(parts)
+}
+
+/// Compares two versions, treating unparseable ones as equal so they never
+/// count as a violation or bump.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => Ordering::Equal,
+    }
+}
+
+async fn record_version(
+    db: &impl DbClient,
+    repo: &str,
+    pr_number: u64,
+    version: &str,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO msrv_history (repo, version, changed_in_pr) VALUES ($1, $2, $3) \
+         ON CONFLICT DO NOTHING",
+        &[&repo, &version, &(pr_number as i64)],
+    )
+    .await
+    .context("recording MSRV change")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_versions() {
+        assert_eq!(
+            rust_version("[package]\nname = \"a\"\nrust-version = \"1.70\"\n").as_deref(),
+            Some("1.70")
+        );
+        let inherited = r#"
+[package]
+rust-version.workspace = true
+
+[workspace.package]
+rust-version = "1.74.1"
+"#;
+        assert_eq!(rust_version(inherited).as_deref(), Some("1.74.1"));
+        assert_eq!(rust_version("[package]\nname = \"a\"\n"), None);
+
+        assert_eq!(
+            toolchain_version("rust-toolchain.toml", "[toolchain]\nchannel = \"1.72.0\"\n")
+                .as_deref(),
+            Some("1.72.0")
+        );
+        assert_eq!(
+            toolchain_version("rust-toolchain", "1.72\n").as_deref(),
+            Some("1.72")
+        );
+        assert_eq!(
+            toolchain_version("rust-toolchain", "nightly-2024-01-01\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(compare_versions("1.70", "1.70.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.9", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("1.75.1", "1.75"), Ordering::Greater);
+        assert_eq!(compare_versions("1.70", "stable"), Ordering::Equal);
+        assert_eq!(parse_version("1"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+    }
+}
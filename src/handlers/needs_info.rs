@@ -0,0 +1,165 @@
+//! Closes issues that are waiting on their author for too long.
+//!
+//! When the configured `label` is added to an issue, a [`NeedsInfoJob`] is
+//! scheduled `days-before-close` days later and its id is kept in the
+//! `needs_info_timers` table. A comment from the issue's author before then
+//! cancels the job and removes the label, as does removing the label by
+//! hand. If the job does run, the issue is closed with `close-comment`.
+
+use crate::{
+    config::{self, NeedsInfoConfig},
+    db::{
+        client::DbClient,
+        jobs::{delete_job, insert_job_returning_id},
+    },
+    github::{Event, IssueCommentAction, IssuesAction},
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing as log;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct NeedsInfoMetadata {
+    repo: String,
+    number: u64,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &NeedsInfoConfig,
+) -> anyhow::Result<()> {
+    match event {
+        Event::Issue(e) => match &e.action {
+            IssuesAction::Labeled { label } if label.name == config.label => {
+                let metadata = serde_json::to_value(NeedsInfoMetadata {
+                    repo: e.repository.full_name.clone(),
+                    number: e.issue.number,
+                })?;
+                let scheduled_at = Utc::now() + Duration::days(config.days_before_close.into());
+                let db = ctx.db.get().await;
+                let job_id = insert_job_returning_id(
+                    &*db,
+                    NeedsInfoJob.name(),
+                    &scheduled_at,
+                    &metadata,
+                    e.repository.private,
+                )
+                .await?;
+                if let Some(previous) =
+                    start_timer(&*db, &e.repository.full_name, e.issue.number, &job_id).await?
+                {
+                    delete_job(&*db, &previous).await?;
+                }
+                Ok(())
+            }
+            IssuesAction::Unlabeled { label } if label.name == config.label => {
+                cancel_timer(ctx, &e.repository.full_name, e.issue.number).await?;
+                Ok(())
+            }
+            _ => Ok(()),
+        },
+        Event::IssueComment(e)
+            if e.action == IssueCommentAction::Created
+                && e.comment.user.login == e.issue.user.login =>
+        {
+            if cancel_timer(ctx, &e.repository.full_name, e.issue.number).await? {
+                log::info!(
+                    "{}'s author responded, no longer waiting on them",
+                    e.issue.global_id()
+                );
+                e.issue.remove_label(&ctx.github, &config.label).await?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub struct NeedsInfoJob;
+
+#[async_trait]
+impl Job for NeedsInfoJob {
+    fn name(&self) -> &'static str {
+        "needs_info_close"
+    }
+
+    async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let metadata: NeedsInfoMetadata = serde_json::from_value(metadata.clone())?;
+        take_timer(&*ctx.db.get().await, &metadata.repo, metadata.number).await?;
+
+        let repo = ctx.github.repository(&metadata.repo).await?;
+        let config = config::get(&ctx.github, &repo).await?;
+        let Some(config) = config.needs_info.as_ref() else {
+            return Ok(());
+        };
+        let issue = repo
+            .get_issue_by_number(&ctx.github, metadata.number)
+            .await?;
+        if !issue.is_open() || !issue.labels().iter().any(|l| l.name == config.label) {
+            return Ok(());
+        }
+
+        log::info!(
+            "closing {} after {} days without a response from its author",
+            issue.global_id(),
+            config.days_before_close
+        );
+        issue
+            .post_comment(&ctx.github, &config.close_comment)
+            .await?;
+        issue.close(&ctx.github).await
+    }
+}
+
+/// Cancels the issue's pending job, returning whether there was one.
+async fn cancel_timer(ctx: &Context, repo: &str, issue_number: u64) -> anyhow::Result<bool> {
+    let db = ctx.db.get().await;
+    match take_timer(&*db, repo, issue_number).await? {
+        Some(job_id) => {
+            delete_job(&*db, &job_id).await?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Tracks the issue's pending job, returning the id of the job it replaces
+/// if the label was added again.
+async fn start_timer(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    job_id: &Uuid,
+) -> anyhow::Result<Option<Uuid>> {
+    let previous = take_timer(db, repo, issue_number).await?;
+    db.execute(
+        "INSERT INTO needs_info_timers (issue_number, repo, job_id) VALUES ($1, $2, $3)",
+        &[&(issue_number as i64), &repo, job_id],
+    )
+    .await
+    .context("starting needs-info timer")?;
+    Ok(previous)
+}
+
+/// Removes the issue's timer, returning the id of its job if it had one.
+async fn take_timer(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+) -> anyhow::Result<Option<Uuid>> {
+    let rows = db
+        .query(
+            "DELETE FROM needs_info_timers WHERE repo = $1 AND issue_number = $2 \
+             RETURNING job_id",
+            &[&repo, &(issue_number as i64)],
+        )
+        .await
+        .context("removing needs-info timer")?;
+    Ok(rows.first().map(|row| row.get(0)))
+}
@@ -0,0 +1,194 @@
+//! Rotates an on-call duty through the members of a team.
+//!
+//! Every week [`OnCallRotationJob`] checks each registered repository, and
+//! once the current assignment in `on_call_assignments` has ended, hands the
+//! duty to the next team member for `rotation-weeks` weeks, announcing it on
+//! the `announcement-issue` if there is one.
+//!
+//! `@rustbot who-is-on-call` replies with the current assignee.
+
+use crate::{
+    config::{self, OnCallConfig},
+    db::{client::DbClient, registered_repos::registered_repos},
+    github::Event,
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use parser::command::on_call::WhoIsOnCallCommand;
+use tracing as log;
+
+const FEATURE: &str = "on-call";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &OnCallConfig,
+) -> anyhow::Result<()> {
+    super::register_repo(ctx, FEATURE, &event.repo().full_name).await
+}
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &OnCallConfig,
+    event: &Event,
+    _cmd: WhoIsOnCallCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    let today = Utc::now().date_naive();
+    let message = match current_assignment(&*ctx.db.get().await, &config.team, today).await? {
+        Some(assignment) => format!(
+            "{} is on call for the {} team until {}.",
+            assignment.assignee, config.team, assignment.week_end
+        ),
+        None => format!("Nobody is on call for the {} team right now.", config.team),
+    };
+    issue.post_comment(&ctx.github, &message).await
+}
+
+pub struct OnCallRotationJob;
+
+#[async_trait]
+impl Job for OnCallRotationJob {
+    fn name(&self) -> &'static str {
+        "on_call_rotation"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let repos = registered_repos(&*db, FEATURE).await?;
+        let today = Utc::now().date_naive();
+
+        for repo_name in repos {
+            if let Err(e) = rotate(ctx, &*db, &repo_name, today).await {
+                log::error!("failed to rotate on-call duty for {repo_name}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hands the on-call duty of `repo_name`'s team to the next member if the
+/// current assignment has ended.
+async fn rotate(
+    ctx: &Context,
+    db: &impl DbClient,
+    repo_name: &str,
+    today: NaiveDate,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = config::get(&ctx.github, &repo).await?;
+    let Some(config) = config.on_call.as_ref() else {
+        return Ok(());
+    };
+    if current_assignment(db, &config.team, today).await?.is_some() {
+        return Ok(());
+    }
+
+    let Some(team) = crate::github::get_team(&ctx.github, &config.team).await? else {
+        log::warn!(
+            "on-call team {} for {repo_name} does not exist",
+            config.team
+        );
+        return Ok(());
+    };
+    let members: Vec<&str> = team.members.iter().map(|m| m.github.as_str()).collect();
+    let previous = latest_assignee(db, &config.team).await?;
+    let Some(assignee) = next_assignee(&members, previous.as_deref()) else {
+        return Ok(());
+    };
+    let week_end = today + Duration::weeks(config.rotation_weeks.max(1).into());
+    db.execute(
+        "INSERT INTO on_call_assignments (team, assignee, week_start, week_end) \
+         VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+        &[&config.team, &assignee, &today, &week_end],
+    )
+    .await
+    .context("inserting on-call assignment")?;
+    log::info!("{assignee} is now on call for {}", config.team);
+
+    if let Some(number) = config.announcement_issue {
+        let mut announcement = format!(
+            "@{assignee} is on call for the {} team until {week_end}.",
+            config.team
+        );
+        if let Some(label) = &config.on_call_label {
+            let query: String = url::form_urlencoded::byte_serialize(
+                format!("is:open label:\"{label}\"").as_bytes(),
+            )
+            .collect();
+            announcement.push_str(&format!(
+                " Please keep an eye on [issues labeled `{label}`]\
+                 (https://github.com/{repo_name}/issues?q={query})."
+            ));
+        }
+        repo.get_issue_by_number(&ctx.github, number)
+            .await?
+            .post_comment(&ctx.github, &announcement)
+            .await?;
+    }
+    Ok(())
+}
+
+struct Assignment {
+    assignee: String,
+    week_end: NaiveDate,
+}
+
+async fn current_assignment(
+    db: &impl DbClient,
+    team: &str,
+    today: NaiveDate,
+) -> anyhow::Result<Option<Assignment>> {
+    let rows = db
+        .query(
+            "SELECT assignee, week_end FROM on_call_assignments \
+             WHERE team = $1 AND week_start <= $2 AND week_end > $2 \
+             ORDER BY week_start DESC LIMIT 1",
+            &[&team, &today],
+        )
+        .await
+        .context("selecting current on-call assignment")?;
+    Ok(rows.first().map(|row| Assignment {
+        assignee: row.get(0),
+        week_end: row.get(1),
+    }))
+}
+
+async fn latest_assignee(db: &impl DbClient, team: &str) -> anyhow::Result<Option<String>> {
+    let rows = db
+        .query(
+            "SELECT assignee FROM on_call_assignments WHERE team = $1 \
+             ORDER BY week_start DESC LIMIT 1",
+            &[&team],
+        )
+        .await
+        .context("selecting latest on-call assignment")?;
+    Ok(rows.first().map(|row| row.get(0)))
+}
+
+/// The member after `previous` in `members`, wrapping around. Starts from the
+/// first member if `previous` isn't (or is no longer) on the team.
+fn next_assignee<'a>(members: &[&'a str], previous: Option<&str>) -> Option<&'a str> {
+    let next = previous
+        .and_then(|previous| members.iter().position(|m| *m == previous))
+        .map_or(0, |i| (i + 1) % members.len());
+    members.get(next).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_wraps_around() {
+        let members = ["alice", "bob", "carol"];
+        assert_eq!(next_assignee(&members, None), Some("alice"));
+        assert_eq!(next_assignee(&members, Some("alice")), Some("bob"));
+        assert_eq!(next_assignee(&members, Some("carol")), Some("alice"));
+        assert_eq!(next_assignee(&members, Some("mallory")), Some("alice"));
+        assert_eq!(next_assignee(&[], Some("alice")), None);
+    }
+}
@@ -0,0 +1,193 @@
+//! Tracks performance runs requested with `@bot perf`.
+//!
+//! The request is recorded in the `perf_requests` table and the performance
+//! team is pinged. Once the perf bot posts its results on the PR, the
+//! comparison URL is stored with the request and the PR is labelled
+//! according to the overall result.
+
+use crate::{
+    config::PerfConfig,
+    db::client::DbClient,
+    github::{Event, IssueCommentAction, Label},
+    handlers::Context,
+};
+use anyhow::Context as _;
+use parser::command::perf::PerfCommand;
+use regex::Regex;
+
+lazy_static::lazy_static! {
+    static ref RESULT_URL: Regex =
+        Regex::new(r"https://perf\.rust-lang\.org/compare\.html\?[^\s)>\]]+").unwrap();
+}
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &PerfConfig,
+    event: &Event,
+    _cmd: PerfCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        issue
+            .post_comment(
+                &ctx.github,
+                "Perf runs can only be requested on pull requests.",
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let requester = &event.user().login;
+    let db = ctx.db.get().await;
+    insert_perf_request(
+        &*db,
+        &issue.repository().to_string(),
+        issue.number,
+        requester,
+    )
+    .await?;
+
+    issue
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "@{requester} requested a perf run for this PR.\n\ncc {}",
+                config.ping
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Handles the perf bot reporting the results of a run.
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &PerfConfig,
+) -> anyhow::Result<()> {
+    let event = match event {
+        Event::IssueComment(e) if e.action == IssueCommentAction::Created => e,
+        _ => return Ok(()),
+    };
+    if event.comment.user.login != config.bot_username {
+        return Ok(());
+    }
+    let result_url = match RESULT_URL.find(&event.comment.body) {
+        Some(m) => m.as_str(),
+        None => return Ok(()),
+    };
+
+    let db = ctx.db.get().await;
+    complete_perf_request(
+        &*db,
+        &event.issue.repository().to_string(),
+        event.issue.number,
+        result_url,
+    )
+    .await?;
+
+    let outcome = PerfOutcome::from_comment(&event.comment.body);
+    let mut labels = Vec::new();
+    if outcome.regressions {
+        labels.push(Label {
+            name: config.regression_label.clone(),
+        });
+    }
+    if outcome.improvements {
+        labels.push(Label {
+            name: config.improvement_label.clone(),
+        });
+    }
+    if !labels.is_empty() {
+        event.issue.add_labels(&ctx.github, labels).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PerfOutcome {
+    regressions: bool,
+    improvements: bool,
+}
+
+impl PerfOutcome {
+    /// Reads the overall result line of a perf bot comment, e.g.
+    /// `Overall result: ❌✅ regressions and improvements - ACTION NEEDED`.
+    fn from_comment(body: &str) -> PerfOutcome {
+        body.lines()
+            .find_map(|line| line.split_once("Overall result:"))
+            .map(|(_, result)| {
+                let result = result.to_lowercase();
+                PerfOutcome {
+                    regressions: result.contains("regression"),
+                    improvements: result.contains("improvement"),
+                }
+            })
+            .unwrap_or_default()
+    }
+}
+
+async fn insert_perf_request(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    requested_by: &str,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO perf_requests (repo, issue_number, requested_by, requested_at) \
+         VALUES ($1, $2, $3, now())",
+        &[&repo, &(issue_number as i64), &requested_by],
+    )
+    .await
+    .context("inserting perf request")?;
+    Ok(())
+}
+
+async fn complete_perf_request(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    result_url: &str,
+) -> anyhow::Result<()> {
+    db.execute(
+        "UPDATE perf_requests SET completed_at = now(), result_url = $3 \
+         WHERE repo = $1 AND issue_number = $2 AND completed_at IS NULL",
+        &[&repo, &(issue_number as i64), &result_url],
+    )
+    .await
+    .context("completing perf request")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_overall_result() {
+        let body = "Finished benchmarking commit (abc123): [comparison URL](https://perf.rust-lang.org/compare.html?start=a&end=b&stat=instructions:u).\n\n\
+                    Overall result: ❌ regressions - ACTION NEEDED";
+        assert_eq!(
+            RESULT_URL.find(body).unwrap().as_str(),
+            "https://perf.rust-lang.org/compare.html?start=a&end=b&stat=instructions:u"
+        );
+        assert_eq!(
+            PerfOutcome::from_comment(body),
+            PerfOutcome {
+                regressions: true,
+                improvements: false
+            }
+        );
+        assert_eq!(
+            PerfOutcome::from_comment("Overall result: ❌✅ regressions and improvements"),
+            PerfOutcome {
+                regressions: true,
+                improvements: true
+            }
+        );
+        assert_eq!(
+            PerfOutcome::from_comment("Overall result: no relevant changes"),
+            PerfOutcome::default()
+        );
+    }
+}
@@ -18,8 +18,7 @@ pub(super) async fn handle_command(
     event: &Event,
     team_name: PingCommand,
 ) -> anyhow::Result<()> {
-    let is_team_member = if let Err(_) | Ok(false) = event.user().is_team_member(&ctx.github).await
-    {
+    let is_team_member = if let Err(_) | Ok(false) = ctx.is_team_member(event.user()).await {
         false
     } else {
         true
@@ -34,7 +33,11 @@ pub(super) async fn handle_command(
         return Ok(());
     }
 
-    let (gh_team, config) = match config.get_by_name(&team_name.team) {
+    let normalized = normalize_team_name(&team_name.team);
+    let (gh_team, config) = match config
+        .get_by_name(&team_name.team)
+        .or_else(|| config.get_by_name(&normalized))
+    {
         Some(v) => v,
         None => {
             let cmnt = ErrorComment::new(
@@ -103,3 +106,29 @@ pub(super) async fn handle_command(
 
     Ok(())
 }
+
+/// Normalizes the ways people tend to write team names, so that `@T-lang`,
+/// `t-lang` and `T-Lang ` all become `lang`.
+fn normalize_team_name(name: &str) -> String {
+    let name = name.trim().trim_start_matches('@');
+    let name = name.strip_prefix("rust-lang/").unwrap_or(name);
+    let name = name.to_lowercase();
+    match name.strip_prefix("t-") {
+        Some(stripped) if !stripped.is_empty() => stripped.to_string(),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn team_names_are_normalized() {
+        for name in ["lang", "@T-lang", "t-lang", "T-Lang ", " @rust-lang/lang"] {
+            assert_eq!(normalize_team_name(name), "lang", "{name:?}");
+        }
+        assert_eq!(normalize_team_name("WG-async"), "wg-async");
+        assert_eq!(normalize_team_name("t-"), "t-");
+    }
+}
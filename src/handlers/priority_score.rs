@@ -0,0 +1,176 @@
+//! Scores new and edited issues with configurable heuristics and applies a
+//! matching priority label.
+//!
+//! Each `[[priority-scoring.rules]]` entry whose keyword appears in the
+//! issue (and whose label, if any, is on it) adds its `score-delta`. The total
+//! picks one of the `P-*` labels, replacing any other one, and is recorded in
+//! `issue_priority_scores` so scores can be tracked over time.
+//!
+//! A `P-*` label other than the one the last recorded score picked was set by
+//! someone else, so such issues are no longer scored.
+
+use crate::{
+    config::{PriorityScoringConfig, ScoringRule},
+    db::client::DbClient,
+    github::{Event, Issue, IssuesAction, Label},
+    handlers::Context,
+};
+use anyhow::Context as _;
+
+const PRIORITY_LABELS: [&str; 4] = ["P-critical", "P-high", "P-medium", "P-low"];
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &PriorityScoringConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !matches!(e.action, IssuesAction::Opened | IssuesAction::Edited) || e.issue.is_pr() {
+        return Ok(());
+    }
+    // Issues no rule applies to are left for people to prioritize.
+    let Some(score) = score(&e.issue, &config.rules) else {
+        return Ok(());
+    };
+
+    let db = ctx.db.get().await;
+    let repo = e.issue.repository().to_string();
+    let last_score = last_score(&*db, &repo, e.issue.number).await?;
+    if prioritized_by_someone_else(&e.issue, last_score) {
+        return Ok(());
+    }
+
+    let label = priority_label(score);
+    let others: Vec<_> = PRIORITY_LABELS
+        .into_iter()
+        .filter(|&l| l != label)
+        .collect();
+    e.issue.remove_labels(&ctx.github, &others).await?;
+    e.issue
+        .add_labels(
+            &ctx.github,
+            vec![Label {
+                name: label.to_string(),
+            }],
+        )
+        .await?;
+
+    record_score(&*db, &repo, e.issue.number, score).await
+}
+
+/// Whether `issue` has a `P-*` label that the last recorded score didn't
+/// pick.
+fn prioritized_by_someone_else(issue: &Issue, last_score: Option<i32>) -> bool {
+    let ours = last_score.map(priority_label);
+    issue
+        .labels()
+        .iter()
+        .any(|l| PRIORITY_LABELS.contains(&l.name.as_str()) && Some(l.name.as_str()) != ours)
+}
+
+/// Sums the deltas of the rules that apply to `issue`, or `None` if none do.
+fn score(issue: &Issue, rules: &[ScoringRule]) -> Option<i32> {
+    let text = format!("{}\n{}", issue.title, issue.body).to_lowercase();
+    let mut applied = rules
+        .iter()
+        .filter(|rule| text.contains(&rule.keyword.to_lowercase()))
+        .filter(|rule| {
+            rule.label_if_present.as_ref().map_or(true, |label| {
+                issue.labels().iter().any(|l| &l.name == label)
+            })
+        })
+        .peekable();
+    applied.peek()?;
+    Some(applied.map(|rule| rule.score_delta).sum())
+}
+
+fn priority_label(score: i32) -> &'static str {
+    match score {
+        10.. => "P-critical",
+        5..=9 => "P-high",
+        1..=4 => "P-medium",
+        _ => "P-low",
+    }
+}
+
+async fn last_score(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+) -> anyhow::Result<Option<i32>> {
+    let rows = db
+        .query(
+            "SELECT score FROM issue_priority_scores \
+             WHERE repo = $1 AND issue_number = $2 \
+             ORDER BY scored_at DESC LIMIT 1",
+            &[&repo, &(issue_number as i64)],
+        )
+        .await
+        .context("getting last issue priority score")?;
+    Ok(rows.first().map(|row| row.get(0)))
+}
+
+async fn record_score(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    score: i32,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO issue_priority_scores (issue_number, repo, score, scored_at) \
+         VALUES ($1, $2, $3, now())",
+        &[&(issue_number as i64), &repo, &score],
+    )
+    .await
+    .context("recording issue priority score")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::IssueBuilder;
+
+    fn rule(keyword: &str, label_if_present: Option<&str>, score_delta: i32) -> ScoringRule {
+        ScoringRule {
+            keyword: keyword.to_string(),
+            label_if_present: label_if_present.map(str::to_string),
+            score_delta,
+        }
+    }
+
+    #[test]
+    fn sums_applicable_rules() {
+        let issue = IssueBuilder::new("rust-lang/rust", 1)
+            .title("ICE: Unsoundness in borrowck")
+            .body("rustc segfaults when compiling this")
+            .labels(&["regression-from-stable-to-stable"])
+            .build();
+        let rules = [
+            rule("unsoundness", None, 6),
+            rule("segfault", None, 3),
+            rule("", Some("regression-from-stable-to-stable"), 2),
+            rule("", Some("E-easy"), -5),
+            rule("typo", None, -2),
+        ];
+        assert_eq!(score(&issue, &rules), Some(11));
+        assert_eq!(priority_label(11), "P-critical");
+        assert_eq!(score(&issue, &rules[3..]), None);
+        assert_eq!(priority_label(-1), "P-low");
+    }
+
+    #[test]
+    fn priorities_set_by_people_are_kept() {
+        let unlabeled = IssueBuilder::new("rust-lang/rust", 1).build();
+        assert!(!prioritized_by_someone_else(&unlabeled, None));
+
+        let high = IssueBuilder::new("rust-lang/rust", 1)
+            .labels(&["C-bug", "P-high"])
+            .build();
+        assert!(prioritized_by_someone_else(&high, None));
+        assert!(prioritized_by_someone_else(&high, Some(11)));
+        assert!(!prioritized_by_someone_else(&high, Some(7)));
+    }
+}
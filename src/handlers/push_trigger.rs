@@ -0,0 +1,110 @@
+//! Schedules jobs when a push to the default branch touches certain paths.
+//!
+//! Each `[[push-trigger]]` entry pairs a `path-glob` with a `job-name`. When
+//! a push to the default branch adds or modifies a matching file, the job is
+//! inserted into the `jobs` table to run `delay-seconds` later, with the
+//! repository and the matched paths as its metadata.
+
+use crate::{
+    config::PathTriggeredJobConfig,
+    db::jobs::insert_job,
+    github::{Event, PushCommit},
+    handlers::Context,
+};
+use anyhow::Context as _;
+use chrono::{Duration, Utc};
+use glob::Pattern;
+use std::collections::BTreeSet;
+use tracing as log;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &[PathTriggeredJobConfig],
+) -> anyhow::Result<()> {
+    let Event::Push(push) = event else {
+        return Ok(());
+    };
+    let repo = event.repo();
+    if push.git_ref != format!("refs/heads/{}", repo.default_branch) {
+        return Ok(());
+    }
+
+    for trigger in config {
+        let pattern = Pattern::new(&trigger.path_glob)
+            .with_context(|| format!("invalid push-trigger path-glob {}", trigger.path_glob))?;
+        let paths = matching_paths(&pattern, &push.commits);
+        if paths.is_empty() {
+            continue;
+        }
+        if !crate::jobs::jobs()
+            .iter()
+            .any(|job| job.name() == trigger.job_name)
+        {
+            log::warn!(
+                "push-trigger in {} names unknown job {}",
+                repo.full_name,
+                trigger.job_name
+            );
+            continue;
+        }
+
+        let scheduled_at = Utc::now() + Duration::seconds(trigger.delay_seconds as i64);
+        let metadata = serde_json::json!({
+            "repo": repo.full_name,
+            "paths": paths,
+        });
+        log::info!(
+            "scheduling {} at {scheduled_at} for push to {}",
+            trigger.job_name,
+            repo.full_name
+        );
+        insert_job(
+            &*ctx.db.get().await,
+            &trigger.job_name,
+            &scheduled_at,
+            &metadata,
+            false,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// The added or modified paths across `commits` that match `pattern`.
+fn matching_paths(pattern: &Pattern, commits: &[PushCommit]) -> BTreeSet<String> {
+    commits
+        .iter()
+        .flat_map(|commit| commit.added.iter().chain(&commit.modified))
+        .filter(|path| pattern.matches(path))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_added_and_modified_paths() {
+        let commit = |added: &[&str], modified: &[&str]| PushCommit {
+            id: "abc".to_string(),
+            added: added.iter().map(|p| p.to_string()).collect(),
+            modified: modified.iter().map(|p| p.to_string()).collect(),
+        };
+        let commits = [
+            commit(&["src/doc/book/ch01.md"], &["README.md"]),
+            commit(&[], &["src/doc/reference/types.md", "src/lib.rs"]),
+        ];
+        let pattern = Pattern::new("src/doc/**").unwrap();
+        assert_eq!(
+            matching_paths(&pattern, &commits),
+            BTreeSet::from([
+                "src/doc/book/ch01.md".to_string(),
+                "src/doc/reference/types.md".to_string(),
+            ])
+        );
+        let pattern = Pattern::new("triagebot.toml").unwrap();
+        assert!(matching_paths(&pattern, &commits).is_empty());
+    }
+}
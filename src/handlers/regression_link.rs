@@ -0,0 +1,107 @@
+//! Links new issues to the PRs behind the commits they mention.
+//!
+//! Regression reports often name the first bad commit. When a new issue's
+//! body contains full commit SHAs, the PRs associated with each of them are
+//! looked up and listed in a single comment as possible causes.
+
+use crate::{
+    config::RegressionLinkConfig,
+    github::{Event, IssuesAction},
+    handlers::Context,
+};
+use regex::Regex;
+use std::fmt::Write as _;
+use tracing as log;
+
+lazy_static::lazy_static! {
+    static ref COMMIT_SHA: Regex = Regex::new(r"\b[0-9a-f]{40}\b").unwrap();
+}
+
+/// Only the first few SHAs are looked up, so that an issue pasting a long
+/// commit list doesn't cost a request per commit.
+const MAX_SHAS: usize = 5;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &RegressionLinkConfig,
+) -> anyhow::Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if e.action != IssuesAction::Opened || e.issue.is_pr() {
+        return Ok(());
+    }
+
+    let mut comment = String::new();
+    for sha in commit_shas(&e.issue.body) {
+        // A SHA from another repository (or a typo) shouldn't stop the others
+        // from being resolved.
+        let pulls = match e.repository.pulls_for_commit(&ctx.github, sha).await {
+            Ok(pulls) => pulls,
+            Err(err) => {
+                log::warn!(
+                    "failed to find pulls for {sha} mentioned in {}: {err:?}",
+                    e.issue.global_id()
+                );
+                continue;
+            }
+        };
+        if pulls.is_empty() {
+            continue;
+        }
+        writeln!(comment, "- {sha}:").unwrap();
+        for pr in pulls {
+            writeln!(comment, "  - #{} {}", pr.number, pr.title).unwrap();
+        }
+    }
+    if comment.is_empty() {
+        return Ok(());
+    }
+
+    e.issue
+        .post_comment(
+            &ctx.github,
+            &format!(
+                "This issue mentions commits from the following PRs, \
+                 which may have introduced it:\n\n{comment}"
+            ),
+        )
+        .await
+}
+
+/// The distinct full commit SHAs in `body`, in order of appearance.
+fn commit_shas(body: &str) -> Vec<&str> {
+    let mut shas = Vec::new();
+    for sha in COMMIT_SHA.find_iter(body).map(|m| m.as_str()) {
+        if !shas.contains(&sha) {
+            shas.push(sha);
+        }
+    }
+    shas.truncate(MAX_SHAS);
+    shas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_distinct_shas() {
+        let body = "\
+            Works on 5a4e47ebed6e4e1a4e8c8f2ff6a5c5b8e2e0e2a1, broken since \
+            0123456789abcdef0123456789abcdef01234567.\n\
+            Bisected to 0123456789abcdef0123456789abcdef01234567 \
+            (not 0123456789abcdef0123456789abcdef012345678 or deadbeef).";
+        assert_eq!(
+            commit_shas(body),
+            [
+                "5a4e47ebed6e4e1a4e8c8f2ff6a5c5b8e2e0e2a1",
+                "0123456789abcdef0123456789abcdef01234567",
+            ]
+        );
+    }
+}
@@ -10,7 +10,7 @@
 
 use crate::{
     config::RelabelConfig,
-    github::{self, Event, GithubClient},
+    github::{self, Event},
     handlers::Context,
     interactions::ErrorComment,
 };
@@ -26,7 +26,7 @@ pub(super) async fn handle_command(
     let mut to_add = vec![];
     for delta in &input.0 {
         let name = delta.label().as_str();
-        let err = match check_filter(name, config, is_member(&event.user(), &ctx.github).await) {
+        let err = match check_filter(name, config, is_member(&event.user(), ctx).await) {
             Ok(CheckFilterResult::Allow) => None,
             Ok(CheckFilterResult::Deny) => Some(format!(
                 "Label {} can only be set by Rust team members",
@@ -96,8 +96,8 @@ enum TeamMembership {
     Unknown,
 }
 
-async fn is_member(user: &github::User, client: &GithubClient) -> TeamMembership {
-    match user.is_team_member(client).await {
+async fn is_member(user: &github::User, ctx: &Context) -> TeamMembership {
+    match ctx.is_team_member(user).await {
         Ok(true) => TeamMembership::Member,
         Ok(false) => TeamMembership::Outsider,
         Err(err) => {
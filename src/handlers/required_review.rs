@@ -0,0 +1,196 @@
+//! Requires approval from a specific team on PRs with a given label.
+//!
+//! For each `[[required-review]]` entry, whenever a review is submitted on a
+//! PR carrying the entry's `label`, or the label is added, the approvals from
+//! members of `team` are counted. Once there are `min-approvals` of them the
+//! PR gets an `approved-by-{team}` label, which is removed again if an
+//! approval is withdrawn. A single comment, edited whenever the count
+//! changes, says how many approvals are still needed.
+
+use crate::{
+    config::RequiredReviewConfig,
+    db::issue_data::IssueData,
+    github::{self, Event, Issue, IssueCommentAction, IssuesAction, Label, PullRequestReview},
+    handlers::Context,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing as log;
+
+const REQUIRED_REVIEW_KEY: &str = "required_review";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RequiredReviewState {
+    /// Team -> the comment saying how many of its approvals are missing.
+    comments: HashMap<String, StatusComment>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StatusComment {
+    id: u64,
+    /// How many approvals the comment currently says are missing.
+    missing: usize,
+}
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &[RequiredReviewConfig],
+) -> anyhow::Result<()> {
+    let (issue, added_label) = match event {
+        Event::IssueComment(e)
+            if e.action == IssueCommentAction::Created
+                && e.issue.is_pr()
+                && e.comment.pr_review_state.is_some() =>
+        {
+            (&e.issue, None)
+        }
+        Event::Issue(e) if e.issue.is_pr() => match &e.action {
+            IssuesAction::Labeled { label } => (&e.issue, Some(&label.name)),
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+
+    for required in config {
+        // Adding an unrelated label doesn't change anything.
+        if added_label.map_or(false, |label| *label != required.label) {
+            continue;
+        }
+        if issue.labels().iter().any(|l| l.name == required.label) {
+            assess(ctx, issue, required).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn assess(
+    ctx: &Context,
+    issue: &Issue,
+    required: &RequiredReviewConfig,
+) -> anyhow::Result<()> {
+    let Some(team) = github::get_team(&ctx.github, &required.team).await? else {
+        log::warn!(
+            "required-review team {} for {} does not exist",
+            required.team,
+            issue.global_id()
+        );
+        return Ok(());
+    };
+    let members: HashSet<&str> = team.members.iter().map(|m| m.github.as_str()).collect();
+    let reviews = issue.reviews(&ctx.github).await?;
+    let approvals = team_approvals(&reviews, &members);
+
+    let approved_label = format!("approved-by-{}", required.team);
+    let has_label = issue.labels().iter().any(|l| l.name == approved_label);
+    let missing = (required.min_approvals as usize).saturating_sub(approvals);
+    if missing == 0 {
+        if !has_label {
+            issue
+                .add_labels(
+                    &ctx.github,
+                    vec![Label {
+                        name: approved_label,
+                    }],
+                )
+                .await?;
+        }
+    } else if has_label {
+        issue.remove_label(&ctx.github, &approved_label).await?;
+    }
+    update_status_comment(ctx, issue, required, missing).await
+}
+
+/// Posts or edits the comment saying how many approvals are `missing`,
+/// unless it already says so.
+async fn update_status_comment(
+    ctx: &Context,
+    issue: &Issue,
+    required: &RequiredReviewConfig,
+    missing: usize,
+) -> anyhow::Result<()> {
+    let mut db = ctx.db.get().await;
+    let mut state: IssueData<'_, RequiredReviewState> =
+        IssueData::load(&mut db, issue, REQUIRED_REVIEW_KEY).await?;
+    let tracked = state.data.comments.get(&required.team);
+    // Without a comment there is nothing to update once approved.
+    if tracked.map_or(missing == 0, |comment| comment.missing == missing) {
+        return Ok(());
+    }
+    let message = if missing == 0 {
+        format!(
+            "PRs labeled `{}` need {} approval(s) from the {} team; \
+             all have been given.",
+            required.label, required.min_approvals, required.team
+        )
+    } else {
+        format!(
+            "PRs labeled `{}` need {} approval(s) from the {} team; \
+             {missing} more needed.",
+            required.label, required.min_approvals, required.team
+        )
+    };
+    let id = match tracked {
+        Some(comment) => {
+            issue
+                .edit_comment(&ctx.github, comment.id, &message)
+                .await?;
+            comment.id
+        }
+        None => issue.create_comment(&ctx.github, &message).await?.id,
+    };
+    state
+        .data
+        .comments
+        .insert(required.team.clone(), StatusComment { id, missing });
+    state.save().await
+}
+
+/// Counts the members whose latest review is an approval.
+///
+/// Comments don't change a reviewer's verdict, but a later request for
+/// changes or a dismissal withdraws an earlier approval.
+fn team_approvals(reviews: &[PullRequestReview], members: &HashSet<&str>) -> usize {
+    let mut latest: HashMap<&str, &str> = HashMap::new();
+    for review in reviews {
+        if review.state == "COMMENTED" || review.state == "PENDING" {
+            continue;
+        }
+        latest.insert(&review.user.login, &review.state);
+    }
+    latest
+        .iter()
+        .filter(|(login, state)| members.contains(*login) && **state == "APPROVED")
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::User;
+
+    fn review(login: &str, state: &str) -> PullRequestReview {
+        PullRequestReview {
+            user: User {
+                login: login.to_string(),
+                id: 0,
+            },
+            state: state.to_string(),
+        }
+    }
+
+    #[test]
+    fn only_current_member_approvals_count() {
+        let members = HashSet::from(["alice", "bob", "carol"]);
+        let reviews = [
+            review("alice", "APPROVED"),
+            review("alice", "COMMENTED"),
+            review("bob", "APPROVED"),
+            review("bob", "CHANGES_REQUESTED"),
+            review("carol", "CHANGES_REQUESTED"),
+            review("carol", "APPROVED"),
+            review("mallory", "APPROVED"),
+        ];
+        assert_eq!(team_approvals(&reviews, &members), 2);
+    }
+}
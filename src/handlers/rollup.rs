@@ -0,0 +1,176 @@
+//! Links PRs merged as part of a rollup back to the rollup.
+//!
+//! When a PR with the configured `rollup-label` is opened, edited or
+//! labeled, the PRs listed under "Successful merges:" in its description are
+//! recorded in the `rollup_prs` table. Once the rollup is merged, each of
+//! them gets a comment pointing at it.
+
+use crate::{
+    config::RollupConfig,
+    db::client::DbClient,
+    github::{Event, IssuesAction, Repository},
+    handlers::Context,
+};
+use anyhow::Context as _;
+use tracing as log;
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &RollupConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !e.issue.is_pr()
+        || !e
+            .issue
+            .labels()
+            .iter()
+            .any(|l| l.name == config.rollup_label)
+    {
+        return Ok(());
+    }
+    let repo = e.repository.full_name.as_str();
+
+    match e.action {
+        IssuesAction::Opened | IssuesAction::Edited | IssuesAction::Labeled { .. } => {
+            let constituents = constituent_prs(&e.issue.body);
+            record_rollup(&*ctx.db.get().await, repo, e.issue.number, &constituents).await
+        }
+        IssuesAction::Closed if e.issue.merged => {
+            let constituents = get_constituents(&*ctx.db.get().await, repo, e.issue.number).await?;
+            log::info!(
+                "rollup {} merged {} PRs",
+                e.issue.global_id(),
+                constituents.len()
+            );
+            for number in constituents {
+                if let Err(err) = link_to_rollup(ctx, &e.repository, number, e.issue.number).await {
+                    log::error!("failed to link {repo}#{number} to its rollup: {err:?}");
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn link_to_rollup(
+    ctx: &Context,
+    repo: &Repository,
+    number: u64,
+    rollup_pr: u64,
+) -> anyhow::Result<()> {
+    repo.get_issue_by_number(&ctx.github, number)
+        .await?
+        .post_comment(
+            &ctx.github,
+            &format!("This PR was merged as part of rollup #{rollup_pr}."),
+        )
+        .await
+}
+
+/// The PRs listed under "Successful merges:" in a rollup's description,
+/// which the rollup tooling writes as
+///
+/// ```text
+/// Successful merges:
+///
+///  - #123 (Fix the thing)
+///  - #456 (Document the other thing)
+///
+/// Failed merges:
+///
+///  - #789 (Break everything)
+/// ```
+fn constituent_prs(body: &str) -> Vec<u64> {
+    let mut prs = Vec::new();
+    let mut in_successful = false;
+    for line in body.lines() {
+        let line = line.trim();
+        if line.ends_with(':') && !line.starts_with('-') {
+            in_successful = line == "Successful merges:";
+            continue;
+        }
+        if !in_successful {
+            continue;
+        }
+        let Some(reference) = line.strip_prefix("- #") else {
+            continue;
+        };
+        let digits: String = reference
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        if let Ok(number) = digits.parse() {
+            prs.push(number);
+        }
+    }
+    prs
+}
+
+async fn record_rollup(
+    db: &impl DbClient,
+    repo: &str,
+    rollup_pr: u64,
+    constituents: &[u64],
+) -> anyhow::Result<()> {
+    // The description may have been edited, so start over.
+    db.execute(
+        "DELETE FROM rollup_prs WHERE repo = $1 AND rollup_pr = $2",
+        &[&repo, &(rollup_pr as i64)],
+    )
+    .await
+    .context("clearing rollup PRs")?;
+    for constituent in constituents {
+        db.execute(
+            "INSERT INTO rollup_prs (rollup_pr, constituent_pr, repo) VALUES ($1, $2, $3) \
+             ON CONFLICT DO NOTHING",
+            &[&(rollup_pr as i64), &(*constituent as i64), &repo],
+        )
+        .await
+        .context("recording rollup PR")?;
+    }
+    Ok(())
+}
+
+async fn get_constituents(
+    db: &impl DbClient,
+    repo: &str,
+    rollup_pr: u64,
+) -> anyhow::Result<Vec<u64>> {
+    let rows = db
+        .query(
+            "SELECT constituent_pr FROM rollup_prs WHERE repo = $1 AND rollup_pr = $2 \
+             ORDER BY constituent_pr",
+            &[&repo, &(rollup_pr as i64)],
+        )
+        .await
+        .context("selecting rollup PRs")?;
+    Ok(rows.iter().map(|row| row.get::<_, i64>(0) as u64).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_successful_merges() {
+        let body = "Successful merges:
+
+ - #118214 (Add support for `for await` loops)
+ - #119035 (Fix the typo)
+ - rust-lang/cargo#13000 (Not in this repository)
+
+Failed merges:
+
+ - #119040 (Break the build)
+
+r? @ghost
+@rustbot modify labels: rollup
+";
+        assert_eq!(constituent_prs(body), [118214, 119035]);
+        assert!(constituent_prs("Fixes #1").is_empty());
+    }
+}
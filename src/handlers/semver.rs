@@ -0,0 +1,141 @@
+//! Labels PRs with the kind of semver change they make.
+//!
+//! A PR whose description or commit messages contain `BREAKING CHANGE:` gets
+//! the `breaking-label`, replacing the minor and patch labels. Anyone on the
+//! team, or the PR's author, can set the label by hand with
+//! `@rustbot semver breaking`, `@rustbot semver minor` or
+//! `@rustbot semver patch`. PRs merged without any of the three labels get a
+//! comment asking the author to add one.
+
+use crate::{
+    config::SemverConfig,
+    github::{Event, Issue, IssuesAction, Label},
+    handlers::Context,
+};
+use parser::command::semver::SemverCommand;
+use tracing as log;
+
+const BREAKING_CHANGE_MARKER: &str = "BREAKING CHANGE:";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    config: &SemverConfig,
+) -> anyhow::Result<()> {
+    let Event::Issue(e) = event else {
+        return Ok(());
+    };
+    if !e.issue.is_pr() {
+        return Ok(());
+    }
+    let labels = [
+        &config.breaking_label,
+        &config.minor_label,
+        &config.patch_label,
+    ];
+    let has_label = |name: &str| e.issue.labels().iter().any(|l| l.name == name);
+
+    match e.action {
+        IssuesAction::Opened | IssuesAction::Edited | IssuesAction::Synchronize => {
+            if has_label(&config.breaking_label) {
+                return Ok(());
+            }
+            let mut breaking = e.issue.body.contains(BREAKING_CHANGE_MARKER);
+            if !breaking {
+                breaking = e
+                    .issue
+                    .commits(&ctx.github)
+                    .await?
+                    .iter()
+                    .any(|commit| commit.commit.message.contains(BREAKING_CHANGE_MARKER));
+            }
+            if breaking {
+                log::info!("{} is a breaking change", e.issue.global_id());
+                set_label(ctx, &e.issue, config, SemverCommand::Breaking).await?;
+            }
+            Ok(())
+        }
+        IssuesAction::Closed if e.issue.merged && !labels.iter().any(|l| has_label(l)) => {
+            e.issue
+                .post_comment(
+                    &ctx.github,
+                    &format!(
+                        "@{} please label this change as `{}`, `{}` or `{}` with \
+                         `@{} semver breaking`, `minor` or `patch`.",
+                        e.issue.user.login,
+                        config.breaking_label,
+                        config.minor_label,
+                        config.patch_label,
+                        ctx.username,
+                    ),
+                )
+                .await
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(super) async fn handle_command(
+    ctx: &Context,
+    config: &SemverConfig,
+    event: &Event,
+    cmd: SemverCommand,
+) -> anyhow::Result<()> {
+    let issue = event.issue().unwrap();
+    if !issue.is_pr() {
+        issue
+            .post_comment(
+                &ctx.github,
+                "Only pull requests can be labeled with a semver change.",
+            )
+            .await?;
+        return Ok(());
+    }
+    let is_author = event.user().login == issue.user.login;
+    if !is_author && !ctx.is_team_member(event.user()).await.unwrap_or(false) {
+        issue
+            .post_comment(
+                &ctx.github,
+                "Only the author or team members may use the `semver` command.",
+            )
+            .await?;
+        return Ok(());
+    }
+    set_label(ctx, issue, config, cmd).await
+}
+
+/// Adds the label for `kind` and removes the other two.
+async fn set_label(
+    ctx: &Context,
+    issue: &Issue,
+    config: &SemverConfig,
+    kind: SemverCommand,
+) -> anyhow::Result<()> {
+    let label = label_for(config, kind);
+    let others: Vec<&str> = [
+        &config.breaking_label,
+        &config.minor_label,
+        &config.patch_label,
+    ]
+    .into_iter()
+    .filter(|l| *l != label)
+    .map(String::as_str)
+    .collect();
+    issue.remove_labels(&ctx.github, &others).await?;
+    issue
+        .add_labels(
+            &ctx.github,
+            vec![Label {
+                name: label.clone(),
+            }],
+        )
+        .await
+}
+
+fn label_for(config: &SemverConfig, kind: SemverCommand) -> &String {
+    match kind {
+        SemverCommand::Breaking => &config.breaking_label,
+        SemverCommand::Minor => &config.minor_label,
+        SemverCommand::Patch => &config.patch_label,
+    }
+}
@@ -0,0 +1,179 @@
+//! Escalates issues that no team member has responded to in time.
+//!
+//! When an issue is opened in a repository with an `[sla]` section, a
+//! [`SlaEscalationJob`] is scheduled `response-sla-hours` later and its id is
+//! kept in the `sla_timers` table. A comment from a team member before then
+//! cancels the job. If it does run and the issue is still open without a
+//! team member's comment, the `escalation-label` is added and the
+//! `escalation-team` is pinged.
+
+use crate::{
+    config::{self, SlaConfig},
+    db::{
+        client::DbClient,
+        jobs::{delete_job, insert_job_returning_id},
+    },
+    github::{Event, IssueCommentAction, IssuesAction, Label},
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing as log;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SlaMetadata {
+    repo: String,
+    number: u64,
+}
+
+pub(super) async fn handle(ctx: &Context, event: &Event, config: &SlaConfig) -> anyhow::Result<()> {
+    match event {
+        Event::Issue(e) if e.action == IssuesAction::Opened && !e.issue.is_pr() => {
+            // Team members' own issues don't need a response from the team.
+            if ctx.is_team_member(&e.issue.user).await.unwrap_or(false) {
+                return Ok(());
+            }
+            let metadata = serde_json::to_value(SlaMetadata {
+                repo: e.repository.full_name.clone(),
+                number: e.issue.number,
+            })?;
+            let scheduled_at = Utc::now() + Duration::hours(config.response_sla_hours.into());
+            let db = ctx.db.get().await;
+            // The event may have been delivered again.
+            if let Some(job_id) = take_timer(&*db, &e.repository.full_name, e.issue.number).await? {
+                delete_job(&*db, &job_id).await?;
+            }
+            let job_id = insert_job_returning_id(
+                &*db,
+                SlaEscalationJob.name(),
+                &scheduled_at,
+                &metadata,
+                e.repository.private,
+            )
+            .await?;
+            start_timer(&*db, &e.repository.full_name, e.issue.number, &job_id).await
+        }
+        Event::IssueComment(e)
+            if e.action == IssueCommentAction::Created
+                && !e.issue.is_pr()
+                && e.comment.user.login != e.issue.user.login =>
+        {
+            if !ctx.is_team_member(&e.comment.user).await.unwrap_or(false) {
+                return Ok(());
+            }
+            let db = ctx.db.get().await;
+            if let Some(job_id) = take_timer(&*db, &e.repository.full_name, e.issue.number).await? {
+                log::info!(
+                    "{} was answered in time, cancelling SLA escalation",
+                    e.issue.global_id()
+                );
+                delete_job(&*db, &job_id).await?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub struct SlaEscalationJob;
+
+#[async_trait]
+impl Job for SlaEscalationJob {
+    fn name(&self) -> &'static str {
+        "sla_escalation"
+    }
+
+    async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let metadata: SlaMetadata = serde_json::from_value(metadata.clone())?;
+        take_timer(&*ctx.db.get().await, &metadata.repo, metadata.number).await?;
+
+        let repo = ctx.github.repository(&metadata.repo).await?;
+        let config = config::get(&ctx.github, &repo).await?;
+        // The SLA may have been removed since the issue was opened.
+        let Some(config) = config.sla.as_ref() else {
+            return Ok(());
+        };
+        let issue = repo
+            .get_issue_by_number(&ctx.github, metadata.number)
+            .await?;
+        if !issue.is_open() {
+            return Ok(());
+        }
+        for comment in issue.get_first100_comments(&ctx.github).await? {
+            if comment.user.login != issue.user.login
+                && ctx.is_team_member(&comment.user).await.unwrap_or(false)
+            {
+                return Ok(());
+            }
+        }
+
+        log::info!(
+            "{} has no team response after {} hours, escalating",
+            issue.global_id(),
+            config.response_sla_hours
+        );
+        issue
+            .add_labels(
+                &ctx.github,
+                vec![Label {
+                    name: config.escalation_label.clone(),
+                }],
+            )
+            .await?;
+        let Some(team) = crate::github::get_team(&ctx.github, &config.escalation_team).await?
+        else {
+            anyhow::bail!("escalation team {} does not exist", config.escalation_team);
+        };
+        let users: Vec<_> = team
+            .members
+            .iter()
+            .map(|member| format!("@{}", member.github))
+            .collect();
+        issue
+            .post_comment(
+                &ctx.github,
+                &format!(
+                    "This issue hasn't had a response from the team in {} hours.\n\ncc {}",
+                    config.response_sla_hours,
+                    users.join(" ")
+                ),
+            )
+            .await
+    }
+}
+
+async fn start_timer(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+    job_id: &Uuid,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO sla_timers (issue_number, repo, job_id) VALUES ($1, $2, $3) \
+         ON CONFLICT (repo, issue_number) DO UPDATE SET job_id = EXCLUDED.job_id",
+        &[&(issue_number as i64), &repo, job_id],
+    )
+    .await
+    .context("starting SLA timer")?;
+    Ok(())
+}
+
+/// Removes the issue's SLA timer, returning the id of its job if it had one.
+async fn take_timer(
+    db: &impl DbClient,
+    repo: &str,
+    issue_number: u64,
+) -> anyhow::Result<Option<Uuid>> {
+    let rows = db
+        .query(
+            "DELETE FROM sla_timers WHERE repo = $1 AND issue_number = $2 RETURNING job_id",
+            &[&repo, &(issue_number as i64)],
+        )
+        .await
+        .context("removing SLA timer")?;
+    Ok(rows.first().map(|row| row.get(0)))
+}
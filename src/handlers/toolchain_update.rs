@@ -0,0 +1,198 @@
+//! Announces new Rust toolchain releases.
+//!
+//! Every day [`ToolchainUpdateJob`] reads the rustup dist manifest of each
+//! channel the registered repositories follow. When the version differs from
+//! the one recorded for a repository in `toolchain_versions`, it is announced
+//! on the repository's `tracking-issue` and then recorded, so a failed
+//! announcement is retried the next day.
+
+use crate::{
+    config::{self, ToolchainChannel, ToolchainUpdateConfig},
+    db::{client::DbClient, registered_repos::registered_repos},
+    github::Event,
+    handlers::Context,
+    jobs::Job,
+};
+use anyhow::Context as _;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tracing as log;
+
+const FEATURE: &str = "toolchain-update";
+
+pub(super) async fn handle(
+    ctx: &Context,
+    event: &Event,
+    _config: &ToolchainUpdateConfig,
+) -> anyhow::Result<()> {
+    super::register_repo(ctx, FEATURE, &event.repo().full_name).await
+}
+
+pub struct ToolchainUpdateJob;
+
+#[async_trait]
+impl Job for ToolchainUpdateJob {
+    fn name(&self) -> &'static str {
+        "toolchain_update"
+    }
+
+    async fn run(&self, ctx: &Context, _metadata: &serde_json::Value) -> anyhow::Result<()> {
+        let db = ctx.db.get().await;
+        let repos = registered_repos(&*db, FEATURE).await?;
+
+        // Each channel's manifest is only fetched once per run.
+        let mut versions = HashMap::new();
+        for repo_name in repos {
+            if let Err(e) = update_repo(ctx, &*db, &repo_name, &mut versions).await {
+                log::error!("failed to announce toolchain updates for {repo_name}: {e:?}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Announces the releases on the channels `repo_name` follows that it
+/// hasn't seen yet.
+async fn update_repo(
+    ctx: &Context,
+    db: &impl DbClient,
+    repo_name: &str,
+    versions: &mut HashMap<ToolchainChannel, String>,
+) -> anyhow::Result<()> {
+    let repo = ctx.github.repository(repo_name).await?;
+    let config = config::get(&ctx.github, &repo).await?;
+    let Some(config) = config.toolchain_update.as_ref() else {
+        return Ok(());
+    };
+    for &channel in &config.channels {
+        let version = match versions.get(&channel) {
+            Some(version) => version.clone(),
+            None => {
+                let version = latest_version(ctx, channel).await?;
+                versions.insert(channel, version.clone());
+                version
+            }
+        };
+        let previous = recorded_version(db, repo_name, channel).await?;
+        if previous.as_ref() == Some(&version) {
+            continue;
+        }
+        // The first version seen for a channel isn't news.
+        if previous.is_some() {
+            log::info!(
+                "announcing {} toolchain {version} to {repo_name}",
+                channel.as_str()
+            );
+            repo.get_issue_by_number(&ctx.github, config.tracking_issue)
+                .await?
+                .post_comment(&ctx.github, &announcement(channel, &version))
+                .await?;
+        }
+        record_version(db, repo_name, channel, &version).await?;
+    }
+    Ok(())
+}
+
+async fn latest_version(ctx: &Context, channel: ToolchainChannel) -> anyhow::Result<String> {
+    let manifest = ctx
+        .github
+        .raw()
+        .get(format!(
+            "https://static.rust-lang.org/dist/channel-rust-{}.toml",
+            channel.as_str()
+        ))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    rust_version(&manifest)
+}
+
+/// The `rust` package's version in a dist manifest, e.g.
+/// `1.75.0 (82e1608df 2023-12-21)`.
+fn rust_version(manifest: &str) -> anyhow::Result<String> {
+    let manifest: toml::Table = toml::from_str(manifest).context("parsing dist manifest")?;
+    manifest
+        .get("pkg")
+        .and_then(|pkg| pkg.get("rust"))
+        .and_then(|rust| rust.get("version"))
+        .and_then(|version| version.as_str())
+        .map(str::to_string)
+        .context("dist manifest has no pkg.rust.version")
+}
+
+async fn recorded_version(
+    db: &impl DbClient,
+    repo: &str,
+    channel: ToolchainChannel,
+) -> anyhow::Result<Option<String>> {
+    let rows = db
+        .query(
+            "SELECT version FROM toolchain_versions WHERE repo = $1 AND channel = $2",
+            &[&repo, &channel.as_str()],
+        )
+        .await
+        .context("selecting toolchain version")?;
+    Ok(rows.first().map(|row| row.get(0)))
+}
+
+async fn record_version(
+    db: &impl DbClient,
+    repo: &str,
+    channel: ToolchainChannel,
+    version: &str,
+) -> anyhow::Result<()> {
+    db.execute(
+        "INSERT INTO toolchain_versions (repo, channel, version, detected_at) \
+         VALUES ($1, $2, $3, now()) \
+         ON CONFLICT (repo, channel) DO UPDATE SET version = EXCLUDED.version, \
+         detected_at = EXCLUDED.detected_at",
+        &[&repo, &channel.as_str(), &version],
+    )
+    .await
+    .context("recording toolchain version")?;
+    Ok(())
+}
+
+fn announcement(channel: ToolchainChannel, version: &str) -> String {
+    // Only stable releases are tagged; the other channels' changes are
+    // collected in RELEASES.md until they are released.
+    let release_notes = match (channel, version.split_whitespace().next()) {
+        (ToolchainChannel::Stable, Some(number)) => {
+            format!("https://github.com/rust-lang/rust/releases/tag/{number}")
+        }
+        _ => "https://github.com/rust-lang/rust/blob/master/RELEASES.md".to_string(),
+    };
+    format!(
+        "Rust {version} is now available on the {} channel. See the [release notes]({release_notes}).",
+        channel.as_str()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_manifest_version() {
+        let manifest = r#"
+manifest-version = "2"
+date = "2023-12-28"
+
+[pkg.cargo]
+version = "0.76.0 (c84b36747 2023-11-22)"
+
+[pkg.rust]
+version = "1.75.0 (82e1608df 2023-12-21)"
+"#;
+        let version = rust_version(manifest).unwrap();
+        assert_eq!(version, "1.75.0 (82e1608df 2023-12-21)");
+        assert_eq!(
+            announcement(ToolchainChannel::Stable, &version),
+            "Rust 1.75.0 (82e1608df 2023-12-21) is now available on the stable channel. \
+             See the [release notes](https://github.com/rust-lang/rust/releases/tag/1.75.0)."
+        );
+        assert!(rust_version("[pkg.cargo]\nversion = \"0.76.0\"\n").is_err());
+    }
+}
@@ -17,13 +17,7 @@ pub(super) async fn handle_command(
             .await?;
         return Ok(());
     }
-    if !event
-        .user()
-        .is_team_member(&ctx.github)
-        .await
-        .ok()
-        .unwrap_or(false)
-    {
+    if !ctx.is_team_member(event.user()).await.ok().unwrap_or(false) {
         issue
             .post_comment(
                 &ctx.github,
@@ -35,17 +35,34 @@ pub struct PingComment<'a> {
     users: &'a [&'a str],
 }
 
+/// The maximum number of users mentioned in a single ping comment. GitHub
+/// tends to flag comments with a lot more mentions than this as spam.
+const MAX_MENTIONS_PER_COMMENT: usize = 50;
+
 impl<'a> PingComment<'a> {
     pub fn new(issue: &'a Issue, users: &'a [&str]) -> PingComment<'a> {
         PingComment { issue, users }
     }
 
+    /// Posts the ping, split over several comments if there are more than
+    /// [`MAX_MENTIONS_PER_COMMENT`] users.
     pub async fn post(&self, client: &GithubClient) -> anyhow::Result<()> {
-        let mut body = String::new();
-        for user in self.users {
-            write!(body, "@{} ", user)?;
+        for body in self.bodies()? {
+            self.issue.post_comment(client, &body).await?;
         }
-        self.issue.post_comment(client, &body).await
+        Ok(())
+    }
+
+    fn bodies(&self) -> anyhow::Result<Vec<String>> {
+        let mut bodies = Vec::new();
+        for chunk in self.users.chunks(MAX_MENTIONS_PER_COMMENT) {
+            let mut body = String::new();
+            for user in chunk {
+                write!(body, "@{} ", user)?;
+            }
+            bodies.push(body);
+        }
+        Ok(bodies)
     }
 }
 
@@ -167,3 +184,22 @@ impl<'a> EditIssueBody<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::IssueBuilder;
+
+    #[test]
+    fn large_pings_are_chunked() {
+        let issue = IssueBuilder::new("rust-lang/rust", 1).build();
+        let names: Vec<String> = (0..60).map(|i| format!("member{i}")).collect();
+        let users: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+
+        let bodies = PingComment::new(&issue, &users).bodies().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0].matches('@').count(), 50);
+        assert_eq!(bodies[1].matches('@').count(), 10);
+        assert!(bodies[1].starts_with("@member50 "));
+    }
+}
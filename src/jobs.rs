@@ -42,13 +42,20 @@
 //!     }
 
 use std::str::FromStr;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use cron::Schedule;
 
 use crate::{
     db::jobs::JobSchedule,
-    handlers::{docs_update::DocsUpdateJob, rustc_commits::RustcCommitsJob, Context},
+    handlers::{
+        contributor_digest::ContributorDigestJob, dep_audit::DependencyAuditJob,
+        docs_update::DocsUpdateJob, edition::EditionSummaryJob,
+        feature_tracking::FeatureTrackingJob, meeting_agenda::MeetingAgendaJob,
+        needs_info::NeedsInfoJob, on_call::OnCallRotationJob, rustc_commits::RustcCommitsJob,
+        sla::SlaEscalationJob, toolchain_update::ToolchainUpdateJob, Context,
+    },
 };
 
 /// How often new cron-based jobs will be placed in the queue.
@@ -61,7 +68,19 @@ pub const JOB_PROCESSING_CADENCE_IN_SECS: u64 = 60;
 
 // The default jobs list that are currently scheduled to run
 pub fn jobs() -> Vec<Box<dyn Job + Send + Sync>> {
-    vec![Box::new(DocsUpdateJob), Box::new(RustcCommitsJob)]
+    vec![
+        Box::new(DocsUpdateJob),
+        Box::new(RustcCommitsJob),
+        Box::new(FeatureTrackingJob),
+        Box::new(MeetingAgendaJob),
+        Box::new(ContributorDigestJob),
+        Box::new(SlaEscalationJob),
+        Box::new(OnCallRotationJob),
+        Box::new(DependencyAuditJob),
+        Box::new(ToolchainUpdateJob),
+        Box::new(NeedsInfoJob),
+        Box::new(EditionSummaryJob),
+    ]
 }
 
 // Definition of the schedule repetition for the jobs we want to run.
@@ -79,6 +98,49 @@ pub fn default_jobs() -> Vec<JobSchedule> {
             schedule: Schedule::from_str("* 0,30 * * * * *").unwrap(),
             metadata: serde_json::Value::Null,
         },
+        JobSchedule {
+            name: FeatureTrackingJob.name(),
+            // Around 9am Pacific time on every Tuesday.
+            schedule: Schedule::from_str("0 00 17 * * Tue *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: MeetingAgendaJob.name(),
+            // Every 30 minutes, each repository's own schedule decides
+            // whether an agenda is due.
+            schedule: Schedule::from_str("0 0,30 * * * * *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: ContributorDigestJob.name(),
+            // Around 9am Pacific time on every Friday.
+            schedule: Schedule::from_str("0 00 17 * * Fri *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: OnCallRotationJob.name(),
+            // Around 9am Pacific time on every Monday.
+            schedule: Schedule::from_str("0 00 17 * * Mon *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: DependencyAuditJob.name(),
+            // Around 9am Pacific time on every Wednesday.
+            schedule: Schedule::from_str("0 00 17 * * Wed *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: ToolchainUpdateJob.name(),
+            // Around 9am Pacific time every day.
+            schedule: Schedule::from_str("0 00 17 * * * *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
+        JobSchedule {
+            name: EditionSummaryJob.name(),
+            // Around 9am Pacific time on every Thursday.
+            schedule: Schedule::from_str("0 00 17 * * Thu *").unwrap(),
+            metadata: serde_json::Value::Null,
+        },
     ]
 }
 
@@ -86,7 +148,26 @@ pub fn default_jobs() -> Vec<JobSchedule> {
 pub trait Job {
     fn name(&self) -> &str;
 
+    /// The maximum time a single run of this job may take, if it differs
+    /// from the deployment's `TRIAGEBOT_JOB_TIMEOUT_SECONDS`.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
     async fn run(&self, ctx: &Context, metadata: &serde_json::Value) -> anyhow::Result<()>;
+
+    /// Like [`Job::run`], but may also return a result to keep in the job's
+    /// `result` column (e.g. what was posted or merged), for auditing.
+    ///
+    /// Jobs that return `Some` are kept in the `jobs` table once they
+    /// succeed instead of being deleted.
+    async fn run_with_result(
+        &self,
+        ctx: &Context,
+        metadata: &serde_json::Value,
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        self.run(ctx, metadata).await.map(|()| None)
+    }
 }
 
 #[test]
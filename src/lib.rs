@@ -24,8 +24,9 @@ pub mod jobs;
 pub mod notification_listing;
 pub mod payload;
 pub mod rfcbot;
+pub mod shutdown;
 pub mod team;
-mod team_data;
+pub mod team_data;
 pub mod triage;
 pub mod zulip;
 
@@ -74,6 +75,19 @@ pub enum EventName {
     ///
     /// <https://docs.github.com/en/developers/webhooks-and-events/webhooks/webhook-events-and-payloads#create>
     Create,
+    /// A check run was created, completed or re-requested.
+    ///
+    /// This gets translated to [`github::Event::CheckRun`] when sent to a handler.
+    ///
+    /// <https://docs.github.com/en/developers/webhooks-and-events/webhooks/webhook-events-and-payloads#check_run>
+    CheckRun,
+    /// A user was added to or removed from a team.
+    ///
+    /// This is not sent to handlers; it only invalidates the team membership
+    /// cache.
+    ///
+    /// <https://docs.github.com/en/developers/webhooks-and-events/webhooks/webhook-events-and-payloads#membership>
+    Membership,
     /// All other unhandled webhooks.
     Other,
 }
@@ -89,6 +103,8 @@ impl std::str::FromStr for EventName {
             "issues" => EventName::Issue,
             "push" => EventName::Push,
             "create" => EventName::Create,
+            "check_run" => EventName::CheckRun,
+            "membership" => EventName::Membership,
             _ => EventName::Other,
         })
     }
@@ -107,6 +123,8 @@ impl fmt::Display for EventName {
                 EventName::PullRequest => "pull_request",
                 EventName::Push => "push",
                 EventName::Create => "create",
+                EventName::CheckRun => "check_run",
+                EventName::Membership => "membership",
                 EventName::Other => "other",
             }
         )
@@ -260,6 +278,28 @@ pub async fn webhook(
 
             github::Event::Create(payload)
         }
+        EventName::CheckRun => {
+            let payload = deserialize_payload::<github::CheckRunEvent>(&payload)
+                .with_context(|| format!("{:?} failed to deserialize", event))
+                .map_err(anyhow::Error::from)?;
+
+            log::info!("handling check run event {:?}", payload);
+
+            github::Event::CheckRun(payload)
+        }
+        EventName::Membership => {
+            let payload = deserialize_payload::<github::MembershipEvent>(&payload)
+                .with_context(|| format!("{:?} failed to deserialize", event))
+                .map_err(anyhow::Error::from)?;
+
+            log::info!("handling membership event {:?}", payload);
+
+            ctx.team_membership_cache
+                .lock()
+                .unwrap()
+                .invalidate(&payload.member.login);
+            return Ok(true);
+        }
         // Other events need not be handled
         EventName::Other => {
             return Ok(false);
@@ -5,15 +5,22 @@ use futures::future::FutureExt;
 use futures::StreamExt;
 use hyper::{header, Body, Request, Response, Server, StatusCode};
 use route_recognizer::Router;
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{
+    env,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
 use tokio::{task, time};
 use tower::{Service, ServiceExt};
 use tracing as log;
 use tracing::Instrument;
+use triagebot::db::rate_limit::RateLimitConfig;
+use triagebot::handlers::job_watchdog;
 use triagebot::handlers::pull_requests_assignment_update::PullRequestAssignmentUpdate;
 use triagebot::jobs::{
     default_jobs, Job, JOB_PROCESSING_CADENCE_IN_SECS, JOB_SCHEDULING_CADENCE_IN_SECS,
 };
+use triagebot::team_data::TeamMembershipCache;
 use triagebot::{db, github, handlers::Context, notification_listing, payload, EventName};
 
 async fn handle_agenda_request(req: String) -> anyhow::Result<String> {
@@ -90,6 +97,53 @@ async fn serve_req(
             .body(Body::from("Triagebot is awaiting triage."))
             .unwrap());
     }
+    if req.uri.path() == "/health" {
+        let pool = ctx.db.status();
+        let scheduler = scheduler_health(&ctx, "health").await;
+        let healthy = scheduler.is_some();
+        let body = serde_json::json!({
+            "status": if healthy { "ok" } else { "unavailable" },
+            "pool": pool,
+            "scheduler": scheduler,
+        });
+        return Ok(Response::builder()
+            .status(if healthy {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            })
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap());
+    }
+    if req.uri.path() == "/healthz" {
+        return Ok(Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::from("ok"))
+            .unwrap());
+    }
+    if req.uri.path() == "/readyz" {
+        let scheduler = scheduler_health(&ctx, "readiness").await;
+        let db_ok = scheduler.is_some();
+        let github_ok = ctx
+            .github
+            .check_api_health(std::time::Duration::from_secs(5 * 60))
+            .await;
+        let body = serde_json::json!({
+            "db": if db_ok { "ok" } else { "error" },
+            "github": if github_ok { "ok" } else { "stale" },
+            "jobs_queue_depth": scheduler.map(|s| s.due_jobs),
+        });
+        return Ok(Response::builder()
+            .status(if db_ok && github_ok {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            })
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap());
+    }
     if req.uri.path() == "/bors-commit-list" {
         let res = db::rustc_commits::get_commits_with_artifacts(&*ctx.db.get().await).await;
         let res = match res {
@@ -241,9 +295,25 @@ async fn serve_req(
     }
 }
 
+/// Queries the job queue for `/health` and `/readyz`, returning `None` if
+/// the database can't be reached.
+async fn scheduler_health(ctx: &Context, check: &str) -> Option<db::jobs::SchedulerHealth> {
+    let db = match ctx.db.try_get().await {
+        Ok(db) => db,
+        Err(e) => {
+            log::warn!("{check} check failed to get a database connection: {e:?}");
+            return None;
+        }
+    };
+    db::jobs::get_scheduler_health(&*db, db::JobRunnerConfig::from_env().max_retries)
+        .await
+        .map_err(|e| log::warn!("{check} check failed to query the job queue: {e:?}"))
+        .ok()
+}
+
 async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
     let pool = db::ClientPool::new();
-    db::run_migrations(&*pool.get().await)
+    db::run_migrations(&mut *pool.get().await)
         .await
         .context("database migrations")?;
 
@@ -260,6 +330,10 @@ async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
         db: pool,
         github: gh,
         octocrab: oc,
+        milestone_cache: Default::default(),
+        team_membership_cache: Arc::new(Mutex::new(TeamMembershipCache::from_env())),
+        rate_limit: RateLimitConfig::from_env(),
+        shutdown: Default::default(),
     });
 
     // Run all jobs that don't have a schedule (one-off jobs)
@@ -273,6 +347,7 @@ async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
     if !is_scheduled_jobs_disabled() {
         spawn_job_scheduler();
         spawn_job_runner(ctx.clone());
+        spawn_job_watchdog(ctx.clone());
     }
 
     let agenda = tower::ServiceBuilder::new()
@@ -291,8 +366,18 @@ async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
         })
         .service_fn(handle_agenda_request);
 
-    let svc = hyper::service::make_service_fn(move |_conn| {
+    task::spawn({
         let ctx = ctx.clone();
+        async move {
+            shutdown_signal().await;
+            log::info!("shutdown requested");
+            ctx.shutdown.shutdown();
+        }
+    });
+
+    let server_ctx = ctx.clone();
+    let svc = hyper::service::make_service_fn(move |_conn| {
+        let ctx = server_ctx.clone();
         let agenda = agenda.clone();
         async move {
             Ok::<_, hyper::Error>(hyper::service::service_fn(move |req| {
@@ -320,12 +405,56 @@ async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
     });
     log::info!("Listening on http://{}", addr);
 
-    let serve_future = Server::bind(&addr).serve(svc);
+    let serve_future = Server::bind(&addr)
+        .serve(svc)
+        .with_graceful_shutdown(ctx.shutdown.wait());
+
+    // Once shutdown starts, in-flight requests get a limited time to finish.
+    tokio::select! {
+        res = serve_future => res?,
+        _ = async {
+            ctx.shutdown.wait().await;
+            time::sleep(HTTP_SHUTDOWN_GRACE_PERIOD).await;
+        } => log::warn!("in-flight requests did not finish in time"),
+    }
 
-    serve_future.await?;
+    log::info!(
+        "waiting for {} running job(s) to finish",
+        ctx.shutdown.active_job_count()
+    );
+    let grace_period =
+        time::Duration::from_secs(db::JobRunnerConfig::from_env().shutdown_grace_seconds);
+    if !ctx.shutdown.wait_for_jobs_within(grace_period).await {
+        log::warn!(
+            "{} job(s) still running after {grace_period:?}, exiting anyway",
+            ctx.shutdown.active_job_count()
+        );
+    }
     Ok(())
 }
 
+/// How long in-flight HTTP requests may take to finish after a shutdown has
+/// been requested.
+const HTTP_SHUTDOWN_GRACE_PERIOD: time::Duration = time::Duration::from_secs(10);
+
+/// Completes when the process receives SIGTERM or Ctrl-C.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 /// Spawns a background tokio task which runs all jobs having no schedule
 /// i.e. manually executed at the end of the triagebot startup
 // - jobs are not guaranteed to start in sequence (care is to be taken to ensure thet are completely independent one from the other)
@@ -406,7 +535,13 @@ fn spawn_job_runner(ctx: Arc<Context>) {
                     time::interval(time::Duration::from_secs(JOB_PROCESSING_CADENCE_IN_SECS));
 
                 loop {
-                    interval.tick().await;
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = ctx.shutdown.wait() => {
+                            tracing::info!("job runner stopped for shutdown");
+                            return;
+                        }
+                    }
                     db::run_scheduled_jobs(&ctx, &*pool.get().await)
                         .await
                         .context("run database scheduled jobs")
@@ -420,12 +555,33 @@ fn spawn_job_runner(ctx: Arc<Context>) {
                     tracing::error!("run_scheduled_jobs task died (error={err})");
                     tokio::time::sleep(std::time::Duration::new(5, 0)).await;
                 }
+                Ok(()) => return,
                 _ => unreachable!(),
             }
         }
     });
 }
 
+/// Spawns a background tokio task which reports jobs the runner hasn't
+/// gotten to, see [`job_watchdog`].
+///
+/// This is a task of its own rather than a job so that it doesn't depend on
+/// the job runner it is watching.
+fn spawn_job_watchdog(ctx: Arc<Context>) {
+    task::spawn(async move {
+        let mut interval = time::interval(job_watchdog::CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = ctx.shutdown.wait() => return,
+            }
+            if let Err(e) = job_watchdog::check_overdue_jobs(&ctx).await {
+                tracing::error!("job watchdog failed: {e:?}");
+            }
+        }
+    });
+}
+
 /// Determines whether or not background scheduled jobs should be disabled for
 /// the purpose of testing.
 ///
@@ -0,0 +1,137 @@
+//! Coordinates a graceful shutdown: once a shutdown is requested, the job
+//! runner stops picking up new jobs and the process waits for running jobs
+//! to finish before exiting.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+pub struct ShutdownCoordinator {
+    sender: broadcast::Sender<()>,
+    shutting_down: AtomicBool,
+    active_job_count: Arc<AtomicUsize>,
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        ShutdownCoordinator::new()
+    }
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> ShutdownCoordinator {
+        let (sender, _) = broadcast::channel(1);
+        ShutdownCoordinator {
+            sender,
+            shutting_down: AtomicBool::new(false),
+            active_job_count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Signals everything waiting in [`ShutdownCoordinator::wait`].
+    pub fn shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        // Nobody may be listening, which is fine.
+        let _ = self.sender.send(());
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Completes once a shutdown has been requested, including if that
+    /// happened before this was called.
+    pub async fn wait(&self) {
+        let mut receiver = self.sender.subscribe();
+        if self.is_shutting_down() {
+            return;
+        }
+        let _ = receiver.recv().await;
+    }
+
+    /// Marks a job as running until the returned guard is dropped.
+    pub fn job_started(&self) -> ActiveJob {
+        self.active_job_count.fetch_add(1, Ordering::SeqCst);
+        ActiveJob(self.active_job_count.clone())
+    }
+
+    pub fn active_job_count(&self) -> usize {
+        self.active_job_count.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no jobs are running.
+    pub async fn wait_for_jobs(&self) {
+        while self.active_job_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Waits until no jobs are running, or until `grace_period` has passed.
+    ///
+    /// Returns whether all jobs finished in time.
+    pub async fn wait_for_jobs_within(&self, grace_period: Duration) -> bool {
+        tokio::time::timeout(grace_period, self.wait_for_jobs())
+            .await
+            .is_ok()
+    }
+}
+
+/// Returned by [`ShutdownCoordinator::job_started`].
+pub struct ActiveJob(Arc<AtomicUsize>);
+
+impl Drop for ActiveJob {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_running_jobs() {
+        let shutdown = ShutdownCoordinator::new();
+        let job = shutdown.job_started();
+        assert_eq!(shutdown.active_job_count(), 1);
+
+        shutdown.shutdown();
+        // Requested before waiting, but still observed.
+        shutdown.wait().await;
+
+        let still_running =
+            tokio::time::timeout(Duration::from_millis(300), shutdown.wait_for_jobs()).await;
+        assert!(still_running.is_err());
+        drop(job);
+        shutdown.wait_for_jobs().await;
+        assert_eq!(shutdown.active_job_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn job_started_before_shutdown_completes() {
+        let shutdown = ShutdownCoordinator::new();
+        let finished = Arc::new(AtomicBool::new(false));
+        let job = shutdown.job_started();
+        let running = {
+            let finished = finished.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                finished.store(true, Ordering::SeqCst);
+                drop(job);
+            })
+        };
+
+        shutdown.shutdown();
+        assert!(shutdown.wait_for_jobs_within(Duration::from_secs(5)).await);
+        assert!(finished.load(Ordering::SeqCst));
+        running.await.unwrap();
+
+        let _stuck = shutdown.job_started();
+        assert!(
+            !shutdown
+                .wait_for_jobs_within(Duration::from_millis(50))
+                .await
+        );
+    }
+}
@@ -2,6 +2,9 @@ use crate::github::GithubClient;
 use anyhow::Context as _;
 use rust_team_data::v1::{Teams, ZulipMapping, BASE_URL};
 use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 async fn by_url<T: DeserializeOwned>(client: &GithubClient, path: &str) -> anyhow::Result<T> {
     let base = std::env::var("TEAMS_API_URL").unwrap_or(BASE_URL.to_string());
@@ -36,3 +39,153 @@ pub async fn teams(client: &GithubClient) -> anyhow::Result<Teams> {
         .await
         .context("team-api: teams.json")
 }
+
+/// Caches the result of [`crate::github::User::is_team_member`] per login.
+///
+/// Team membership rarely changes, so answers are reused for `ttl`. At most
+/// `capacity` logins are kept; when full, the least recently used entry is
+/// evicted. Entries are also dropped when GitHub sends a `membership` webhook
+/// for the user.
+///
+/// The whole [`teams`] response is kept for `ttl` as well, for checks against
+/// specific teams.
+pub struct TeamMembershipCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<String, MembershipEntry>,
+    teams: Option<(Arc<Teams>, Instant)>,
+    /// Incremented on every access; used to find the least recently used
+    /// entry.
+    clock: u64,
+}
+
+struct MembershipEntry {
+    is_member: bool,
+    fetched_at: Instant,
+    last_used: u64,
+}
+
+impl TeamMembershipCache {
+    pub fn new(ttl: Duration, capacity: usize) -> TeamMembershipCache {
+        TeamMembershipCache {
+            ttl,
+            capacity,
+            entries: HashMap::new(),
+            teams: None,
+            clock: 0,
+        }
+    }
+
+    /// Reads `TRIAGEBOT_TEAM_CACHE_TTL_SECS` (default one hour) and
+    /// `TRIAGEBOT_TEAM_CACHE_CAPACITY` (default 1000).
+    pub fn from_env() -> TeamMembershipCache {
+        let env = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        TeamMembershipCache::new(
+            Duration::from_secs(env("TRIAGEBOT_TEAM_CACHE_TTL_SECS", 60 * 60)),
+            env("TRIAGEBOT_TEAM_CACHE_CAPACITY", 1000) as usize,
+        )
+    }
+
+    pub fn get(&mut self, login: &str) -> Option<bool> {
+        let ttl = self.ttl;
+        let entry = self.entries.get_mut(login)?;
+        if entry.fetched_at.elapsed() >= ttl {
+            self.entries.remove(login);
+            return None;
+        }
+        self.clock += 1;
+        entry.last_used = self.clock;
+        Some(entry.is_member)
+    }
+
+    pub fn insert(&mut self, login: &str, is_member: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(login) && self.entries.len() >= self.capacity {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(login, _)| login.clone());
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(
+            login.to_string(),
+            MembershipEntry {
+                is_member,
+                fetched_at: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+
+    pub fn invalidate(&mut self, login: &str) {
+        self.entries.remove(login);
+    }
+
+    pub fn get_teams(&mut self) -> Option<Arc<Teams>> {
+        match &self.teams {
+            Some((teams, fetched_at)) if fetched_at.elapsed() < self.ttl => Some(teams.clone()),
+            _ => {
+                self.teams = None;
+                None
+            }
+        }
+    }
+
+    pub fn insert_teams(&mut self, teams: Arc<Teams>) {
+        self.teams = Some((teams, Instant::now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_entries_are_refetched() {
+        let mut cache = TeamMembershipCache::new(Duration::ZERO, 10);
+        cache.insert("alice", true);
+        assert_eq!(cache.get("alice"), None);
+
+        let mut cache = TeamMembershipCache::new(Duration::from_secs(60), 10);
+        cache.insert("alice", true);
+        assert_eq!(cache.get("alice"), Some(true));
+        cache.invalidate("alice");
+        assert_eq!(cache.get("alice"), None);
+    }
+
+    #[test]
+    fn least_recently_used_entry_is_evicted() {
+        let mut cache = TeamMembershipCache::new(Duration::from_secs(60), 2);
+        cache.insert("alice", true);
+        cache.insert("bob", false);
+        assert_eq!(cache.get("alice"), Some(true));
+        cache.insert("carol", true);
+        assert_eq!(cache.get("bob"), None);
+        assert_eq!(cache.get("alice"), Some(true));
+        assert_eq!(cache.get("carol"), Some(true));
+    }
+
+    #[test]
+    fn teams_response_expires() {
+        let teams: Arc<Teams> = Arc::new(serde_json::from_str("{}").unwrap());
+
+        let mut cache = TeamMembershipCache::new(Duration::ZERO, 10);
+        cache.insert_teams(teams.clone());
+        assert!(cache.get_teams().is_none());
+
+        let mut cache = TeamMembershipCache::new(Duration::from_secs(60), 10);
+        cache.insert_teams(teams);
+        assert!(cache.get_teams().is_some());
+    }
+}
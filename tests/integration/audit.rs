@@ -0,0 +1,51 @@
+use crate::harness::TestDb;
+use triagebot::db::audit::{
+    get_audit_log_for_issue, record_command_invocation, record_command_result,
+};
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn commands_are_read_back_newest_first() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let first =
+        record_command_invocation(&db, "rust-lang/rust", Some(1), "@rustbot claim", "alice")
+            .await
+            .unwrap();
+    record_command_result(&db, first, &Err(anyhow::anyhow!("boom")))
+        .await
+        .unwrap();
+    let second = record_command_invocation(
+        &db,
+        "rust-lang/rust",
+        Some(1),
+        "@rustbot label +T-compiler",
+        "bob",
+    )
+    .await
+    .unwrap();
+    record_command_result(&db, second, &Ok(())).await.unwrap();
+    record_command_invocation(&db, "rust-lang/rust", Some(2), "@rustbot claim", "carol")
+        .await
+        .unwrap();
+
+    let entries = get_audit_log_for_issue(&db, "rust-lang/rust", 1, 10)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].id, second);
+    assert_eq!(entries[0].command, "@rustbot label +T-compiler");
+    assert_eq!(entries[0].invoked_by, "bob");
+    assert_eq!(entries[0].issue_number, Some(1));
+    assert_eq!(entries[0].result.as_deref(), Some("ok"));
+    assert_eq!(entries[0].error, None);
+    assert_eq!(entries[1].id, first);
+    assert_eq!(entries[1].result.as_deref(), Some("error"));
+    assert!(entries[1].error.as_deref().unwrap().contains("boom"));
+
+    let entries = get_audit_log_for_issue(&db, "rust-lang/rust", 1, 1)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, second);
+}
@@ -0,0 +1,36 @@
+use crate::harness::TestDb;
+use triagebot::db::changelog_nags::{get_changelog_nag, record_changelog_nag, take_changelog_nag};
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn nags_are_recorded_then_taken_once() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    assert_eq!(
+        get_changelog_nag(&db, "rust-lang/rust", 1).await.unwrap(),
+        None
+    );
+    record_changelog_nag(&db, "rust-lang/rust", 1, 41)
+        .await
+        .unwrap();
+    record_changelog_nag(&db, "rust-lang/rust", 1, 42)
+        .await
+        .unwrap();
+    assert_eq!(
+        get_changelog_nag(&db, "rust-lang/rust", 1).await.unwrap(),
+        Some(42)
+    );
+
+    assert_eq!(
+        take_changelog_nag(&db, "rust-lang/rust", 1).await.unwrap(),
+        Some(42)
+    );
+    assert_eq!(
+        get_changelog_nag(&db, "rust-lang/rust", 1).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        take_changelog_nag(&db, "rust-lang/rust", 1).await.unwrap(),
+        None
+    );
+}
@@ -0,0 +1,40 @@
+use crate::harness::TestDb;
+use triagebot::db::conflict_notifications::{
+    record_conflict_notification, take_conflict_notification,
+};
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn notifications_are_replaced_then_taken_once() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    record_conflict_notification(&db, "rust-lang/rust", 1, 41)
+        .await
+        .unwrap();
+    // A new conflict after the first was resolved replaces the comment.
+    record_conflict_notification(&db, "rust-lang/rust", 1, 42)
+        .await
+        .unwrap();
+    record_conflict_notification(&db, "rust-lang/cargo", 1, 43)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        take_conflict_notification(&db, "rust-lang/rust", 1)
+            .await
+            .unwrap(),
+        Some(42)
+    );
+    assert_eq!(
+        take_conflict_notification(&db, "rust-lang/rust", 1)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        take_conflict_notification(&db, "rust-lang/cargo", 1)
+            .await
+            .unwrap(),
+        Some(43)
+    );
+}
@@ -0,0 +1,66 @@
+use crate::harness::{pr_json, TestContext};
+use triagebot::EventName;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+async fn status(db: &tokio_postgres::Client, crater_issue: i64) -> String {
+    db.query_one(
+        "SELECT status FROM crater_requests WHERE crater_repo = $1 AND crater_issue = $2",
+        &[&"rust-lang/crater-test", &crater_issue],
+    )
+    .await
+    .unwrap()
+    .get(0)
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn only_issues_opened_by_triagebot_complete_requests() {
+    let test = TestContext::new().await;
+    let db = test.db.client().await;
+    for crater_issue in [5i64, 6] {
+        db.execute(
+            "INSERT INTO crater_requests \
+             (source_repo, source_pr, crater_repo, crater_issue, requested_by, status) \
+             VALUES ('rust-lang/rust', 123, 'rust-lang/crater-test', $1, 'reviewer', 'requested')",
+            &[&crater_issue],
+        )
+        .await
+        .unwrap();
+    }
+
+    // Not opened by triagebot, so not looked up even though a request with
+    // the same number exists.
+    test.webhook(EventName::Issue, "crater_other_issue_closed.json")
+        .await;
+    assert_eq!(status(&db, 6).await, "requested");
+
+    Mock::given(method("GET"))
+        .and(path("/repos/rust-lang/rust"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "full_name": "rust-lang/rust",
+            "default_branch": "master",
+        })))
+        .mount(&test.github)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/repos/rust-lang/rust/issues/123"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(pr_json("rust-lang/rust", 123)))
+        .mount(&test.github)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/repos/rust-lang/rust/issues/123/comments"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+            "id": 1,
+            "body": "The crater run for this PR has finished.",
+            "html_url": "https://github.com/rust-lang/rust/pull/123#issuecomment-1",
+            "user": { "login": "rustbot", "id": 1003 },
+        })))
+        .expect(1)
+        .mount(&test.github)
+        .await;
+    test.webhook(EventName::Issue, "crater_request_closed.json")
+        .await;
+    test.github.verify().await;
+    assert_eq!(status(&db, 5).await, "completed");
+}
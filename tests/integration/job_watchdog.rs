@@ -0,0 +1,58 @@
+use crate::harness::TestContext;
+use chrono::{Duration, Utc};
+use triagebot::db::jobs::{get_jobs_by_name, get_overdue_jobs, insert_job};
+use triagebot::handlers::job_watchdog::check_overdue_jobs;
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn jobs_overdue_by_an_hour_are_flagged_once() {
+    let test = TestContext::new().await;
+    let db = test.db.client().await;
+    let now = Utc::now();
+    for (number, minutes_ago) in [(1, 120), (2, 30), (3, 150)] {
+        insert_job(
+            &db,
+            "needs_info_close",
+            &(now - Duration::minutes(minutes_ago)),
+            &serde_json::json!({ "repo": "rust-lang/rust", "number": number }),
+            false,
+        )
+        .await
+        .unwrap();
+    }
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    let (started, stuck) = (&jobs[0], &jobs[1]);
+    // Started, so it isn't waiting on the scheduler.
+    db.execute(
+        "UPDATE jobs SET executed_at = now() WHERE id = $1",
+        &[&started.id],
+    )
+    .await
+    .unwrap();
+
+    let overdue = get_overdue_jobs(&db, Duration::hours(1)).await.unwrap();
+    assert_eq!(
+        overdue.iter().map(|job| job.id).collect::<Vec<_>>(),
+        [stuck.id]
+    );
+
+    check_overdue_jobs(&test.ctx).await.unwrap();
+    let alerted = db
+        .query(
+            "SELECT id FROM jobs WHERE overdue_alerted_at IS NOT NULL",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        alerted
+            .iter()
+            .map(|row| row.get::<_, uuid::Uuid>(0))
+            .collect::<Vec<_>>(),
+        [stuck.id]
+    );
+    assert!(get_overdue_jobs(&db, Duration::hours(1))
+        .await
+        .unwrap()
+        .is_empty());
+}
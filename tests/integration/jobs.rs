@@ -0,0 +1,290 @@
+use crate::harness::TestDb;
+use chrono::{Duration, Utc};
+use triagebot::db::jobs::{
+    comment_already_posted, get_jobs_by_name, get_jobs_to_execute, get_scheduler_health,
+    insert_job, record_posted_comment, update_job_error_message, update_job_executed_at,
+};
+use triagebot::db::JobRunnerConfig;
+
+fn issue(number: u64) -> serde_json::Value {
+    serde_json::json!({ "repo": "rust-lang/rust", "number": number })
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn jobs_are_looked_up_by_name() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let now = Utc::now();
+    insert_job(
+        &db,
+        "needs_info_close",
+        &(now + Duration::days(2)),
+        &issue(2),
+        false,
+    )
+    .await
+    .unwrap();
+    insert_job(
+        &db,
+        "needs_info_close",
+        &(now + Duration::days(1)),
+        &issue(1),
+        false,
+    )
+    .await
+    .unwrap();
+    insert_job(&db, "docs_update", &now, &serde_json::json!({}), false)
+        .await
+        .unwrap();
+
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    assert_eq!(
+        jobs.iter().map(|job| &job.metadata).collect::<Vec<_>>(),
+        [&issue(1), &issue(2)]
+    );
+    assert!(jobs.iter().all(|job| job.name == "needs_info_close"));
+    assert!(get_jobs_by_name(&db, "rustc_commits")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn scheduler_health_counts_due_and_permanently_failed_jobs() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let now = Utc::now();
+    for (number, minutes_ago) in [(1, 90), (2, 30), (3, -10)] {
+        let scheduled_at = now - Duration::minutes(minutes_ago);
+        insert_job(
+            &db,
+            "needs_info_close",
+            &scheduled_at,
+            &issue(number),
+            false,
+        )
+        .await
+        .unwrap();
+    }
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    let error = "boom".to_string();
+    // Will be retried.
+    update_job_error_message(&db, &jobs[0].id, &error, &now)
+        .await
+        .unwrap();
+    // Failed more often than the one retry allowed below.
+    for _ in 0..2 {
+        update_job_error_message(&db, &jobs[2].id, &error, &now)
+            .await
+            .unwrap();
+    }
+
+    let health = get_scheduler_health(&db, 1).await.unwrap();
+    assert_eq!(health.due_jobs, 2);
+    assert_eq!(health.oldest_due, Some(jobs[0].scheduled_at));
+    assert_eq!(health.failed_jobs, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn only_the_oldest_due_jobs_are_fetched() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let now = Utc::now();
+    for (number, minutes_ago) in [(1, 30), (2, 90), (3, 10), (4, 60), (5, -10)] {
+        let scheduled_at = now - Duration::minutes(minutes_ago);
+        insert_job(
+            &db,
+            "needs_info_close",
+            &scheduled_at,
+            &issue(number),
+            false,
+        )
+        .await
+        .unwrap();
+    }
+
+    let config = JobRunnerConfig {
+        batch_size: 3,
+        ..JobRunnerConfig::default()
+    };
+    let jobs = get_jobs_to_execute(&db, &config).await.unwrap();
+    assert_eq!(
+        jobs.iter().map(|job| &job.metadata).collect::<Vec<_>>(),
+        [&issue(2), &issue(4), &issue(1)]
+    );
+}
+
+async fn selected(
+    db: &tokio_postgres::Client,
+    retry_minutes: i32,
+    max_retries: i32,
+) -> Vec<serde_json::Value> {
+    let config = JobRunnerConfig {
+        retry_minutes,
+        max_retries,
+        ..JobRunnerConfig::default()
+    };
+    let jobs = get_jobs_to_execute(db, &config).await.unwrap();
+    jobs.into_iter().map(|job| job.metadata).collect()
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn retry_settings_change_which_failed_jobs_are_selected() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let now = Utc::now();
+    for (number, minutes_ago) in [(1, 120), (2, 110)] {
+        let scheduled_at = now - Duration::minutes(minutes_ago);
+        insert_job(
+            &db,
+            "needs_info_close",
+            &scheduled_at,
+            &issue(number),
+            false,
+        )
+        .await
+        .unwrap();
+    }
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    // Failed half an hour ago without a retry time, so `retry_minutes`
+    // decides when it runs again.
+    db.execute(
+        "UPDATE jobs SET error_message = 'boom', failure_count = 1, \
+         executed_at = now() - interval '30 minutes' WHERE id = $1",
+        &[&jobs[0].id],
+    )
+    .await
+    .unwrap();
+    // Failed three times and is due for another retry.
+    for _ in 0..3 {
+        update_job_error_message(
+            &db,
+            &jobs[1].id,
+            &"boom".to_string(),
+            &(now - Duration::minutes(1)),
+        )
+        .await
+        .unwrap();
+    }
+
+    assert_eq!(selected(&db, 60, 5).await, [issue(2)]);
+    assert_eq!(selected(&db, 15, 5).await, [issue(1), issue(2)]);
+    assert_eq!(selected(&db, 15, 2).await, [issue(1)]);
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn jobs_for_different_issues_do_not_collide() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let scheduled_at = Utc::now() + Duration::days(14);
+    for number in [1, 2, 1] {
+        insert_job(
+            &db,
+            "needs_info_close",
+            &scheduled_at,
+            &issue(number),
+            false,
+        )
+        .await
+        .unwrap();
+    }
+
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    let mut metadata = jobs.iter().map(|job| &job.metadata).collect::<Vec<_>>();
+    metadata.sort_by_key(|metadata| metadata["number"].as_u64());
+    assert_eq!(metadata, [&issue(1), &issue(2)]);
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn jobs_that_cannot_be_decrypted_fail() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let envelope = serde_json::json!({ "encrypted": true, "nonce": "00", "ciphertext": "00" });
+    db.execute(
+        "INSERT INTO jobs (name, scheduled_at, metadata, metadata_hash) \
+         VALUES ('needs_info_close', now() - interval '1 minute', $1, 'hash')",
+        &[&envelope],
+    )
+    .await
+    .unwrap();
+
+    let config = JobRunnerConfig {
+        max_retries: 0,
+        ..JobRunnerConfig::default()
+    };
+    assert!(get_jobs_to_execute(&db, &config).await.unwrap().is_empty());
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    assert_eq!(jobs[0].failure_count, 1);
+    assert!(jobs[0].error_message.is_some());
+    // With no retries allowed, it now counts as permanently failed.
+    assert_eq!(get_scheduler_health(&db, 0).await.unwrap().failed_jobs, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn failures_and_results_are_recorded() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let now = Utc::now();
+    for (number, minutes_ago) in [(1, 20), (2, 10)] {
+        insert_job(
+            &db,
+            "needs_info_close",
+            &(now - Duration::minutes(minutes_ago)),
+            &issue(number),
+            false,
+        )
+        .await
+        .unwrap();
+    }
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    let (failed, succeeded) = (&jobs[0], &jobs[1]);
+
+    update_job_executed_at(&db, &failed.id, None).await.unwrap();
+    update_job_error_message(
+        &db,
+        &failed.id,
+        &"boom".to_string(),
+        &(now + Duration::hours(1)),
+    )
+    .await
+    .unwrap();
+    let result = serde_json::json!({ "pr": 123 });
+    update_job_executed_at(&db, &succeeded.id, Some(&result))
+        .await
+        .unwrap();
+
+    let jobs = get_jobs_by_name(&db, "needs_info_close").await.unwrap();
+    assert_eq!(jobs[0].error_message.as_deref(), Some("boom"));
+    assert_eq!(jobs[0].failure_count, 1);
+    assert!(jobs[0].result.is_none());
+    assert!(jobs[1].executed_at.is_some());
+    assert_eq!(jobs[1].result, Some(result));
+    // One waits for its retry, the other is done.
+    let config = JobRunnerConfig::default();
+    assert!(get_jobs_to_execute(&db, &config).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn posted_comments_are_tracked_by_key() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    let key = "contributor_digest:rust-lang/rust:2024-01-01";
+    assert!(!comment_already_posted(&db, key).await.unwrap());
+    record_posted_comment(&db, key).await.unwrap();
+    // Recording it again, after a retried run, is fine.
+    record_posted_comment(&db, key).await.unwrap();
+    assert!(comment_already_posted(&db, key).await.unwrap());
+    assert!(
+        !comment_already_posted(&db, "contributor_digest:rust-lang/rust:2024-01-08")
+            .await
+            .unwrap()
+    );
+}
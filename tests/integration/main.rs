@@ -0,0 +1,20 @@
+//! Tests that run webhooks through the handlers against a real database and
+//! a mock GitHub API.
+//!
+//! Each test starts its own Postgres container, so Docker has to be
+//! available to run them. That isn't the case in the Docker build, so they
+//! are `#[ignore]`d and CI runs them in a job of their own with
+//! `cargo test --test integration -- --ignored`.
+
+mod audit;
+mod changelog_nags;
+mod conflict_notifications;
+mod crater;
+mod harness;
+mod job_watchdog;
+mod jobs;
+mod needs_info;
+mod rate_limit;
+mod registered_repos;
+mod rollup;
+mod sla;
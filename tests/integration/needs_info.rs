@@ -0,0 +1,61 @@
+use crate::harness::TestContext;
+use triagebot::EventName;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const CONFIG: &str = r#"
+[needs-info]
+label = "needs-info"
+days-before-close = 14
+close-comment = "Closing since there was no response."
+"#;
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn labeling_schedules_close_and_author_reply_cancels_it() {
+    let test = TestContext::new().await;
+    test.mock_config("rust-lang/needs-info-test", CONFIG).await;
+    let db = test.db.client().await;
+
+    test.webhook(EventName::Issue, "needs_info_labeled.json")
+        .await;
+    let jobs = db
+        .query("SELECT id, name, metadata FROM jobs", &[])
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+    let job_id: uuid::Uuid = jobs[0].get(0);
+    assert_eq!(jobs[0].get::<_, String>(1), "needs_info_close");
+    assert_eq!(
+        jobs[0].get::<_, serde_json::Value>(2),
+        serde_json::json!({ "repo": "rust-lang/needs-info-test", "number": 10 })
+    );
+    let timer = db
+        .query_one(
+            "SELECT job_id FROM needs_info_timers WHERE repo = $1 AND issue_number = $2",
+            &[&"rust-lang/needs-info-test", &10i64],
+        )
+        .await
+        .unwrap();
+    assert_eq!(timer.get::<_, uuid::Uuid>(0), job_id);
+
+    Mock::given(method("DELETE"))
+        .and(path(
+            "/repos/rust-lang/needs-info-test/issues/10/labels/needs-info",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+        .expect(1)
+        .mount(&test.github)
+        .await;
+    test.webhook(EventName::IssueComment, "needs_info_author_comment.json")
+        .await;
+    test.github.verify().await;
+    let remaining = db
+        .query(
+            "SELECT 1 FROM jobs UNION ALL SELECT 1 FROM needs_info_timers",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(remaining.is_empty());
+}
@@ -0,0 +1,71 @@
+use crate::harness::TestDb;
+use std::time::Duration;
+use triagebot::db::rate_limit::check_and_increment_rate_limit;
+
+/// Waits for the next minute if the current one is about to end, so that a
+/// test's commands all land in the same window.
+async fn avoid_window_boundary(db: &tokio_postgres::Client) {
+    let row = db
+        .query_one("SELECT extract(second FROM now())::float8", &[])
+        .await
+        .unwrap();
+    let second: f64 = row.get(0);
+    if second > 55.0 {
+        tokio::time::sleep(Duration::from_secs_f64(60.0 - second)).await;
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn commands_over_the_limit_are_rejected() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    avoid_window_boundary(&db).await;
+    for _ in 0..3 {
+        assert!(check_and_increment_rate_limit(&db, "alice", 3)
+            .await
+            .unwrap());
+    }
+    assert!(!check_and_increment_rate_limit(&db, "alice", 3)
+        .await
+        .unwrap());
+    // Other users have limits of their own.
+    assert!(check_and_increment_rate_limit(&db, "bob", 3).await.unwrap());
+
+    let row = db
+        .query_one(
+            "SELECT count FROM user_rate_limits WHERE login = $1",
+            &[&"alice"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i32>(0), 3);
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn old_windows_are_pruned() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    db.execute(
+        "INSERT INTO user_rate_limits (login, window_start, count)
+            VALUES ('alice', date_trunc('minute', now()) - INTERVAL '3 minutes', 3),
+                ('alice', date_trunc('minute', now()) - INTERVAL '1 minute', 3)",
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert!(check_and_increment_rate_limit(&db, "bob", 3).await.unwrap());
+
+    let rows = db
+        .query(
+            "SELECT now() - window_start < INTERVAL '2 minutes'
+                FROM user_rate_limits WHERE login = 'alice'",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0].get::<_, bool>(0));
+}
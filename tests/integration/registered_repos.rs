@@ -0,0 +1,34 @@
+use crate::harness::TestDb;
+use triagebot::db::registered_repos::{register_repo, registered_repos};
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn repos_are_registered_once_per_feature() {
+    let db = TestDb::start().await;
+    let db = db.client().await;
+    register_repo(&db, "dep-audit", "rust-lang/rust")
+        .await
+        .unwrap();
+    register_repo(&db, "dep-audit", "rust-lang/cargo")
+        .await
+        .unwrap();
+    register_repo(&db, "dep-audit", "rust-lang/rust")
+        .await
+        .unwrap();
+    register_repo(&db, "on-call", "rust-lang/triagebot")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        registered_repos(&db, "dep-audit").await.unwrap(),
+        ["rust-lang/cargo", "rust-lang/rust"]
+    );
+    assert_eq!(
+        registered_repos(&db, "on-call").await.unwrap(),
+        ["rust-lang/triagebot"]
+    );
+    assert!(registered_repos(&db, "toolchain-update")
+        .await
+        .unwrap()
+        .is_empty());
+}
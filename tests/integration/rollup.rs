@@ -0,0 +1,49 @@
+use crate::harness::{pr_json, TestContext};
+use triagebot::EventName;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const CONFIG: &str = r#"
+[rollup]
+rollup-label = "rollup"
+"#;
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn a_failing_constituent_does_not_stop_the_others() {
+    let test = TestContext::new().await;
+    test.mock_config("rust-lang/rollup-test", CONFIG).await;
+    let db = test.db.client().await;
+    for constituent in [11i64, 12] {
+        db.execute(
+            "INSERT INTO rollup_prs (rollup_pr, constituent_pr, repo) \
+             VALUES (20, $1, 'rust-lang/rollup-test')",
+            &[&constituent],
+        )
+        .await
+        .unwrap();
+    }
+
+    // Nothing is mounted for #11, so looking it up fails with a 404.
+    Mock::given(method("GET"))
+        .and(path("/repos/rust-lang/rollup-test/issues/12"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(pr_json("rust-lang/rollup-test", 12)),
+        )
+        .mount(&test.github)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/repos/rust-lang/rollup-test/issues/12/comments"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+            "id": 1,
+            "body": "This PR was merged as part of rollup #20.",
+            "html_url": "https://github.com/rust-lang/rollup-test/pull/12#issuecomment-1",
+            "user": { "login": "rustbot", "id": 1003 },
+        })))
+        .expect(1)
+        .mount(&test.github)
+        .await;
+    test.webhook(EventName::PullRequest, "rollup_merged.json")
+        .await;
+    test.github.verify().await;
+}
@@ -0,0 +1,50 @@
+use crate::harness::TestContext;
+use triagebot::EventName;
+
+const CONFIG: &str = r#"
+[sla]
+response-sla-hours = 48
+escalation-label = "I-unanswered"
+escalation-team = "triage"
+"#;
+
+async fn timer(db: &tokio_postgres::Client) -> uuid::Uuid {
+    db.query_one(
+        "SELECT job_id FROM sla_timers WHERE repo = $1 AND issue_number = $2",
+        &[&"rust-lang/sla-test", &20i64],
+    )
+    .await
+    .unwrap()
+    .get(0)
+}
+
+#[tokio::test]
+#[ignore = "needs Docker"]
+async fn redelivered_open_replaces_timer_and_team_comment_cancels_it() {
+    let test = TestContext::new().await;
+    test.mock_config("rust-lang/sla-test", CONFIG).await;
+    {
+        let mut teams = test.ctx.team_membership_cache.lock().unwrap();
+        teams.insert("author", false);
+        teams.insert("maintainer", true);
+    }
+    let db = test.db.client().await;
+    test.webhook(EventName::Issue, "sla_opened.json").await;
+    let first = timer(&db).await;
+    // A redelivered event replaces the timer and its job.
+    test.webhook(EventName::Issue, "sla_opened.json").await;
+    let second = timer(&db).await;
+    assert_ne!(first, second);
+    let jobs = db.query("SELECT id, name FROM jobs", &[]).await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].get::<_, uuid::Uuid>(0), second);
+    assert_eq!(jobs[0].get::<_, String>(1), "sla_escalation");
+
+    test.webhook(EventName::IssueComment, "sla_team_comment.json")
+        .await;
+    let remaining = db
+        .query("SELECT 1 FROM jobs UNION ALL SELECT 1 FROM sla_timers", &[])
+        .await
+        .unwrap();
+    assert!(remaining.is_empty());
+}